@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    country::Currency,
+    money::{Money, Rate},
+    pricing::{self, ExchangeRates, PlnRounding},
+};
+
+/// A spending limit for a cart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Budget {
+    pub limit: Money,
+    /// Currency of the limit; `None` for PLN.
+    pub currency: Option<Currency>,
+}
+
+impl Budget {
+    /// Code of the budget's currency, e.g. `"EUR"` or `"PLN"`.
+    pub fn currency_code(&self) -> &'static str {
+        self.currency.map_or("PLN", Currency::code)
+    }
+
+    /// What `amounts` add up to in the budget's currency.
+    ///
+    /// Every amount comes with its currency and PLN rate. Amounts in other
+    /// currencies go through PLN at that rate and back at the rate of the
+    /// budget's currency in `rates`.
+    pub fn spent<I>(&self, amounts: I, rates: &ExchangeRates, rounding: PlnRounding) -> Money
+    where
+        I: IntoIterator<Item = (Money, Currency, Rate)>,
+    {
+        let amounts = amounts.into_iter();
+        let Some(budget_currency) = self.currency else {
+            return pricing::sum_pln(amounts.map(|(amount, _, rate)| (amount, rate)), rounding);
+        };
+        let mut same = Money::ZERO;
+        let mut others = Vec::new();
+        for (amount, currency, rate) in amounts {
+            if currency == budget_currency {
+                same += amount;
+            } else {
+                others.push((amount, rate));
+            }
+        }
+        if others.is_empty() {
+            return same;
+        }
+        let pln = pricing::sum_pln(others, rounding);
+        same + rates.get(budget_currency).convert_back(pln, rounding.mode)
+    }
+
+    /// How much of the budget `amounts` use, see [`Budget::spent`].
+    pub fn status<I>(
+        &self,
+        amounts: I,
+        rates: &ExchangeRates,
+        rounding: PlnRounding,
+    ) -> BudgetStatus
+    where
+        I: IntoIterator<Item = (Money, Currency, Rate)>,
+    {
+        let amounts: Vec<(Money, Currency, Rate)> = amounts.into_iter().collect();
+        let budget_rate_unknown = self
+            .currency
+            .is_some_and(|currency| rates.get(currency) == Rate::ZERO);
+        let rate_unknown = amounts
+            .iter()
+            .filter(|(_, currency, _)| Some(*currency) != self.currency)
+            .any(|(_, _, rate)| *rate == Rate::ZERO || budget_rate_unknown);
+        BudgetStatus {
+            spent: self.spent(amounts, rates, rounding),
+            budget: *self,
+            rate_unknown,
+        }
+    }
+}
+
+/// How much of a budget is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetStatus {
+    pub spent: Money,
+    pub budget: Budget,
+    /// Some amounts had to be converted without a known rate, which counts
+    /// them as zero, so `spent` is too low.
+    pub rate_unknown: bool,
+}
+
+impl BudgetStatus {
+    /// What is left; negative once the budget is exceeded.
+    pub fn remaining(&self) -> Money {
+        self.budget.limit - self.spent
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.spent > self.budget.limit
+    }
+
+    /// Used part of the budget, `1.0` when it is exactly spent.
+    pub fn fraction(&self) -> f32 {
+        if self.budget.limit <= Money::ZERO {
+            return if self.spent > Money::ZERO {
+                f32::INFINITY
+            } else {
+                0.0
+            };
+        }
+        self.spent.minor() as f32 / self.budget.limit.minor() as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budget(limit: i64, currency: Option<Currency>) -> Budget {
+        Budget {
+            limit: Money::from_minor(limit),
+            currency,
+        }
+    }
+
+    fn rates() -> ExchangeRates {
+        let mut rates = ExchangeRates::new();
+        rates.set_override(Currency::Eur, Rate::from_f64(4.25));
+        rates
+    }
+
+    /// 3.90 EUR bought at 4.00 and 100 CZK bought at 0.17.
+    fn amounts() -> [(Money, Currency, Rate); 2] {
+        [
+            (Money::from_minor(390), Currency::Eur, Rate::from_f64(4.0)),
+            (
+                Money::from_minor(10000),
+                Currency::Czk,
+                Rate::from_f64(0.17),
+            ),
+        ]
+    }
+
+    #[test]
+    fn pln_budget_adds_up_the_amounts_at_their_rates() {
+        let status = budget(5000, None).status(amounts(), &rates(), PlnRounding::default());
+        assert_eq!(status.spent, Money::from_minor(1560 + 1700));
+        assert!(!status.rate_unknown);
+        assert!(!status.is_over());
+        assert_eq!(status.remaining(), Money::from_minor(5000 - 3260));
+    }
+
+    #[test]
+    fn currency_budget_converts_other_currencies_back_through_pln() {
+        let status =
+            budget(500, Some(Currency::Eur)).status(amounts(), &rates(), PlnRounding::default());
+        // 17.00 PLN is 4.00 EUR at 4.25.
+        assert_eq!(status.spent, Money::from_minor(390 + 400));
+        assert!(!status.rate_unknown);
+        assert!(status.is_over());
+    }
+
+    #[test]
+    fn unknown_rates_are_flagged() {
+        let mut no_czk_rate = amounts();
+        no_czk_rate[1].2 = Rate::ZERO;
+        let status = budget(5000, None).status(no_czk_rate, &rates(), PlnRounding::default());
+        assert_eq!(status.spent, Money::from_minor(1560));
+        assert!(status.rate_unknown);
+
+        // Without a CZK rate the EUR amount is still counted in full.
+        let status =
+            budget(500, Some(Currency::Eur)).status(no_czk_rate, &rates(), PlnRounding::default());
+        assert_eq!(status.spent, Money::from_minor(390));
+        assert!(status.rate_unknown);
+
+        // Nothing can be converted back into a budget currency without a rate.
+        let status =
+            budget(50000, Some(Currency::Huf)).status(amounts(), &rates(), PlnRounding::default());
+        assert_eq!(status.spent, Money::ZERO);
+        assert!(status.rate_unknown);
+
+        // An amount in the budget's own currency needs no rate at all.
+        let status = budget(500, Some(Currency::Eur)).status(
+            [(Money::from_minor(390), Currency::Eur, Rate::ZERO)],
+            &ExchangeRates::new(),
+            PlnRounding::default(),
+        );
+        assert_eq!(status.spent, Money::from_minor(390));
+        assert!(!status.rate_unknown);
+    }
+
+    #[test]
+    fn fraction_of_the_limit() {
+        let status = |limit, spent| BudgetStatus {
+            spent: Money::from_minor(spent),
+            budget: budget(limit, None),
+            rate_unknown: false,
+        };
+        assert_eq!(status(1000, 250).fraction(), 0.25);
+        assert_eq!(status(1000, 1500).fraction(), 1.5);
+        assert_eq!(status(0, 0).fraction(), 0.0);
+        assert_eq!(status(0, 1).fraction(), f32::INFINITY);
+    }
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{country::Country, error::Result, product::Product, storage};
+
+/// How long a looked up product is served from the cache.
+pub const CACHE_TTL_MINUTES: i64 = 30;
+
+/// Default limit for the on-disk cache size.
+pub const DEFAULT_MAX_CACHE_BYTES: usize = 50 * 1024 * 1024;
+
+/// A cached product together with the moment it stops being valid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedItem {
+    pub product: Product,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl CachedItem {
+    /// Rough number of bytes the item takes up, dominated by the image.
+    fn approximate_size(&self) -> usize {
+        let product = &self.product;
+        product.ean.len()
+            + product.name.len()
+            + product.description.len()
+            + product.image_urls.iter().map(String::len).sum::<usize>()
+            + product.image.as_ref().map_or(0, |image| image.len())
+            + 64
+    }
+}
+
+/// Product cache keyed by storefront and EAN.
+///
+/// Items stay valid for [`CACHE_TTL_MINUTES`]; when the cache grows past its
+/// size limit the items closest to expiring are evicted first.
+///
+/// Clones share the cached items, so a copy to save on another thread is
+/// cheap even with the images in it.
+#[derive(Debug, Clone)]
+pub struct ProductCache {
+    items: HashMap<(Country, String), Arc<CachedItem>>,
+    max_bytes: usize,
+}
+
+impl Default for ProductCache {
+    fn default() -> Self {
+        Self::with_max_bytes(DEFAULT_MAX_CACHE_BYTES)
+    }
+}
+
+impl ProductCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_bytes(max_bytes: usize) -> Self {
+        Self {
+            items: HashMap::new(),
+            max_bytes,
+        }
+    }
+
+    /// Loads a cache saved with [`ProductCache::save`], dropping expired
+    /// items. A missing file yields an empty cache.
+    pub fn load(path: &Path, max_bytes: usize) -> Result<Self> {
+        let mut cache = Self::with_max_bytes(max_bytes);
+        let items: Vec<CachedItem> = storage::load_json(path)?.unwrap_or_default();
+        let now = Utc::now();
+        for item in items {
+            if item.expires_at > now {
+                cache.items.insert(
+                    (item.product.country, item.product.ean.clone()),
+                    Arc::new(item),
+                );
+            }
+        }
+        cache.evict();
+        Ok(cache)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let items: Vec<&CachedItem> = self.items.values().map(|item| &**item).collect();
+        storage::save_json(path, &items)
+    }
+
+    /// Returns a copy of the cached product if it has not expired yet.
+    pub fn get(&self, country: Country, ean: &str) -> Option<Product> {
+        let now = Utc::now();
+        match self.items.get(&(country, ean.to_string())) {
+            Some(cached_item) if cached_item.expires_at > now => Some(cached_item.product.clone()),
+            _ => None,
+        }
+    }
+
+    /// Stores `product` under `country` and `ean` for [`CACHE_TTL_MINUTES`].
+    pub fn insert(&mut self, country: Country, ean: &str, product: Product) {
+        self.items.insert(
+            (country, ean.to_string()),
+            Arc::new(CachedItem {
+                product,
+                expires_at: Utc::now() + Duration::minutes(CACHE_TTL_MINUTES),
+            }),
+        );
+        self.evict();
+    }
+
+    /// Drops expired items, then the items closest to expiring until the
+    /// cache fits into its size limit.
+    fn evict(&mut self) {
+        let now = Utc::now();
+        self.items.retain(|_, item| item.expires_at > now);
+
+        let mut size: usize = self
+            .items
+            .values()
+            .map(|item| item.approximate_size())
+            .sum();
+        if size <= self.max_bytes {
+            return;
+        }
+        let mut by_expiry: Vec<((Country, String), DateTime<Utc>)> = self
+            .items
+            .iter()
+            .map(|(key, item)| (key.clone(), item.expires_at))
+            .collect();
+        by_expiry.sort_by_key(|(_, expires_at)| *expires_at);
+        for (key, _) in by_expiry {
+            if size <= self.max_bytes {
+                break;
+            }
+            if let Some(item) = self.items.remove(&key) {
+                size -= item.approximate_size();
+            }
+        }
+    }
+}
+
+/// File the product cache is kept in.
+pub fn cache_path() -> PathBuf {
+    storage::data_dir().join("cache.json")
+}
@@ -0,0 +1,601 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    budget::{Budget, BudgetStatus},
+    country::{Country, Currency},
+    error::Result,
+    history::{CartHistory, CartOp},
+    money::{Money, Rate},
+    pricing::{self, ExchangeRates, PlnRounding},
+    product::Product,
+    storage,
+};
+
+/// Part of a cart line's quantity bought for one person.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Share {
+    pub buyer: String,
+    pub quantity: i32,
+}
+
+/// A product in the cart; the quantity is `product.quantity`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CartLine {
+    pub product: Product,
+    /// PLN rate of the product's currency when the line was added; zero in
+    /// lines saved without one.
+    #[serde(default)]
+    pub exchange_rate: Rate,
+    pub added_at: DateTime<Utc>,
+    /// Who the line is bought for; the rest of the quantity is unassigned.
+    #[serde(default)]
+    pub shares: Vec<Share>,
+    /// Free-text reminder, e.g. "only if on sale".
+    #[serde(default)]
+    pub note: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl CartLine {
+    /// Price of the whole line in the product's currency.
+    pub fn total(&self) -> Money {
+        pricing::line_total(self.product.price, self.product.quantity)
+    }
+
+    /// Quantity assigned to `buyer`.
+    pub fn share_of(&self, buyer: &str) -> i32 {
+        self.shares
+            .iter()
+            .filter(|share| share.buyer == buyer)
+            .map(|share| share.quantity)
+            .sum()
+    }
+
+    /// Quantity not assigned to anybody.
+    pub fn unassigned(&self) -> i32 {
+        self.product.quantity - self.shares.iter().map(|share| share.quantity).sum::<i32>()
+    }
+
+    /// Whether the line has `tag` and its name, EAN or note contains `text`,
+    /// ignoring case; an empty `text` or `None` tag matches everything.
+    pub fn matches(&self, text: &str, tag: Option<&str>) -> bool {
+        if tag.is_some_and(|tag| !self.tags.iter().any(|t| t == tag)) {
+            return false;
+        }
+        let text = text.trim().to_lowercase();
+        text.is_empty()
+            || self.product.name.to_lowercase().contains(&text)
+            || self.product.ean.contains(&text)
+            || self.note.to_lowercase().contains(&text)
+    }
+
+    /// Drops shares from the last one backwards until they fit the quantity.
+    fn trim_shares(&mut self) {
+        let mut excess = -self.unassigned();
+        while excess > 0 {
+            let Some(last) = self.shares.last_mut() else {
+                break;
+            };
+            let taken = last.quantity.min(excess);
+            last.quantity -= taken;
+            excess -= taken;
+            if last.quantity == 0 {
+                self.shares.pop();
+            }
+        }
+    }
+}
+
+/// What one person owes for a cart, see [`Cart::buyer_totals`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuyerTotal {
+    /// `None` for the quantity nobody was assigned.
+    pub buyer: Option<String>,
+    /// Sum per currency, in [`Currency::ALL`] order.
+    pub totals: Vec<(Currency, Money)>,
+    pub total_pln: Money,
+}
+
+/// One person's part of a cart line, see [`Cart::buyer_lines`].
+#[derive(Debug, Clone, Copy)]
+pub struct BuyerLine<'a> {
+    pub line: &'a CartLine,
+    pub quantity: i32,
+}
+
+impl BuyerLine<'_> {
+    /// Price of this part in the product's currency.
+    pub fn total(&self) -> Money {
+        pricing::line_total(self.line.product.price, self.quantity)
+    }
+}
+
+/// Name given to carts nobody named.
+pub const DEFAULT_CART_NAME: &str = "Koszyk";
+
+fn default_cart_name() -> String {
+    DEFAULT_CART_NAME.to_string()
+}
+
+/// Shopping cart with one line per EAN and storefront.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cart {
+    #[serde(default = "default_cart_name")]
+    pub name: String,
+    items: Vec<CartLine>,
+    created_at: DateTime<Utc>,
+    /// Rates pinned for this cart; `None` follows the global rates.
+    #[serde(default)]
+    pub exchange_rates: Option<ExchangeRates>,
+    /// Fuel, shipping and other PLN costs of the trip, shared out between
+    /// the lines when reselling.
+    #[serde(default)]
+    pub trip_cost: Money,
+    #[serde(default)]
+    pub budget: Option<Budget>,
+    #[serde(skip)]
+    history: CartHistory,
+}
+
+impl Default for Cart {
+    fn default() -> Self {
+        Self::named(DEFAULT_CART_NAME)
+    }
+}
+
+impl Cart {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn named(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            items: Vec::new(),
+            created_at: Utc::now(),
+            exchange_rates: None,
+            trip_cost: Money::ZERO,
+            budget: None,
+            history: CartHistory::default(),
+        }
+    }
+
+    /// `<name>-<created>` with everything but letters and digits in the name
+    /// replaced, for naming files written from this cart.
+    pub fn file_stem(&self) -> String {
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        format!("{}-{}", name, self.created_at.format("%Y%m%d-%H%M%S"))
+    }
+
+    /// Writes the cart into `dir` as `<name>-<created>.json` and returns the
+    /// file's path.
+    pub fn archive(&self, dir: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let archive_path = dir.join(format!("{}.json", self.file_stem()));
+        storage::save_json(&archive_path, self)?;
+        Ok(archive_path)
+    }
+
+    /// The rates this cart is priced with: its pinned rates if it has any,
+    /// `global` otherwise.
+    pub fn rates<'a>(&'a self, global: &'a ExchangeRates) -> &'a ExchangeRates {
+        self.exchange_rates.as_ref().unwrap_or(global)
+    }
+
+    pub fn items(&self) -> &[CartLine] {
+        &self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// Adds `product` to the cart, remembering `exchange_rate` as the rate
+    /// the line is bought at.
+    ///
+    /// If a line with the same EAN from the same storefront already exists its
+    /// quantity is increased instead. Products with a quantity below one are
+    /// ignored; returns whether the cart changed.
+    pub fn add(&mut self, product: Product, exchange_rate: Rate) -> bool {
+        self.add_line(CartLine {
+            product,
+            exchange_rate,
+            added_at: Utc::now(),
+            shares: Vec::new(),
+            note: String::new(),
+            tags: Vec::new(),
+        })
+    }
+
+    /// Adds a line taken from another cart, merging its quantity, shares and
+    /// tags into an existing line for the same product; that line's rate is
+    /// kept, and so is its note unless it has none.
+    pub fn add_line(&mut self, added: CartLine) -> bool {
+        if added.product.quantity <= 0 {
+            return false;
+        }
+        let product = &added.product;
+        match self.items.iter().position(|line| {
+            line.product.ean == product.ean && line.product.country == product.country
+        }) {
+            Some(index) => {
+                let before = self.items[index].clone();
+                let line = &mut self.items[index];
+                line.product.quantity += added.product.quantity;
+                for share in added.shares {
+                    match line.shares.iter_mut().find(|s| s.buyer == share.buyer) {
+                        Some(existing) => existing.quantity += share.quantity,
+                        None => line.shares.push(share),
+                    }
+                }
+                if line.note.is_empty() {
+                    line.note = added.note;
+                }
+                for tag in added.tags {
+                    if !line.tags.contains(&tag) {
+                        line.tags.push(tag);
+                    }
+                }
+                let after = line.clone();
+                self.history.record(CartOp::Merge {
+                    index,
+                    before,
+                    after,
+                });
+            }
+            None => {
+                self.history.record(CartOp::Add {
+                    index: self.items.len(),
+                    line: added.clone(),
+                });
+                self.items.push(added);
+            }
+        }
+        true
+    }
+
+    /// Runs `edit` on the line at `index` and records the change if it
+    /// reports one.
+    fn edit_line(&mut self, index: usize, edit: impl FnOnce(&mut CartLine) -> bool) -> bool {
+        let Some(line) = self.items.get_mut(index) else {
+            return false;
+        };
+        let before = line.clone();
+        if !edit(line) {
+            return false;
+        }
+        let after = line.clone();
+        self.history.record(CartOp::Edit {
+            index,
+            before,
+            after,
+        });
+        true
+    }
+
+    /// Changes the quantity of the line at `index`; quantities below one
+    /// are ignored, use [`Cart::remove_at`] instead.
+    ///
+    /// Lowering the quantity below what is assigned takes it away from the
+    /// most recently added shares first.
+    pub fn set_quantity(&mut self, index: usize, quantity: i32) -> bool {
+        self.edit_line(index, |line| {
+            if quantity <= 0 || line.product.quantity == quantity {
+                return false;
+            }
+            line.product.quantity = quantity;
+            line.trim_shares();
+            true
+        })
+    }
+
+    /// Sets how much of the line at `index` is bought for `buyer`, capped at
+    /// what is not assigned to others; zero removes the buyer from the line.
+    pub fn assign(&mut self, index: usize, buyer: &str, quantity: i32) -> bool {
+        let buyer = buyer.trim();
+        if buyer.is_empty() {
+            return false;
+        }
+        self.edit_line(index, |line| {
+            let current = line.share_of(buyer);
+            // A line saved with fewer pieces than assigned leaves nothing.
+            let cap = (line.unassigned() + current).max(0);
+            let quantity = quantity.clamp(0, cap);
+            if quantity == current {
+                return false;
+            }
+            match line
+                .shares
+                .iter_mut()
+                .position(|share| share.buyer == buyer)
+            {
+                Some(position) if quantity == 0 => {
+                    line.shares.remove(position);
+                }
+                Some(position) => line.shares[position].quantity = quantity,
+                None => line.shares.push(Share {
+                    buyer: buyer.to_string(),
+                    quantity,
+                }),
+            }
+            true
+        })
+    }
+
+    /// Replaces the note of the line at `index`.
+    pub fn set_note(&mut self, index: usize, note: &str) -> bool {
+        let note = note.trim();
+        self.edit_line(index, |line| {
+            if line.note == note {
+                return false;
+            }
+            line.note = note.to_string();
+            true
+        })
+    }
+
+    /// Adds `tag` to the line at `index` unless it already has it.
+    pub fn add_tag(&mut self, index: usize, tag: &str) -> bool {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return false;
+        }
+        self.edit_line(index, |line| {
+            if line.tags.iter().any(|t| t == tag) {
+                return false;
+            }
+            line.tags.push(tag.to_string());
+            true
+        })
+    }
+
+    /// Removes `tag` from the line at `index`.
+    pub fn remove_tag(&mut self, index: usize, tag: &str) -> bool {
+        self.edit_line(index, |line| {
+            let count = line.tags.len();
+            line.tags.retain(|t| t != tag);
+            line.tags.len() != count
+        })
+    }
+
+    /// Every tag used in the cart, sorted.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .items
+            .iter()
+            .flat_map(|line| line.tags.iter().cloned())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// Everybody something in the cart is bought for, in the order they were
+    /// first assigned.
+    pub fn buyers(&self) -> Vec<String> {
+        let mut buyers: Vec<String> = Vec::new();
+        for share in self.items.iter().flat_map(|line| &line.shares) {
+            if !buyers.contains(&share.buyer) {
+                buyers.push(share.buyer.clone());
+            }
+        }
+        buyers
+    }
+
+    /// The parts of the cart bought for `buyer`, or the unassigned parts for
+    /// `None`.
+    pub fn buyer_lines(&self, buyer: Option<&str>) -> Vec<BuyerLine<'_>> {
+        self.items
+            .iter()
+            .map(|line| BuyerLine {
+                line,
+                quantity: match buyer {
+                    Some(buyer) => line.share_of(buyer),
+                    None => line.unassigned(),
+                },
+            })
+            .filter(|part| part.quantity > 0)
+            .collect()
+    }
+
+    /// Subtotals per person, followed by the unassigned rest if there is
+    /// any. PLN amounts are converted at each line's rate and rounded per
+    /// person as configured in `rounding`.
+    pub fn buyer_totals(&self, rounding: PlnRounding) -> Vec<BuyerTotal> {
+        let buyers = self.buyers();
+        buyers
+            .iter()
+            .map(|buyer| Some(buyer.as_str()))
+            .chain([None])
+            .filter_map(|buyer| {
+                let parts = self.buyer_lines(buyer);
+                if parts.is_empty() {
+                    return None;
+                }
+                let totals = Currency::ALL
+                    .iter()
+                    .filter_map(|&currency| {
+                        let mut lines = parts
+                            .iter()
+                            .filter(|part| part.line.product.currency() == currency)
+                            .peekable();
+                        lines.peek()?;
+                        Some((currency, lines.map(BuyerLine::total).sum()))
+                    })
+                    .collect();
+                let total_pln = pricing::sum_pln(
+                    parts
+                        .iter()
+                        .map(|part| (part.total(), part.line.exchange_rate)),
+                    rounding,
+                );
+                Some(BuyerTotal {
+                    buyer: buyer.map(str::to_string),
+                    totals,
+                    total_pln,
+                })
+            })
+            .collect()
+    }
+
+    pub fn remove_at(&mut self, index: usize) -> Option<CartLine> {
+        if index >= self.items.len() {
+            return None;
+        }
+        let line = self.items.remove(index);
+        self.history.record(CartOp::Remove {
+            index,
+            line: line.clone(),
+        });
+        Some(line)
+    }
+
+    /// Removes every line, keeping the cart's creation time.
+    pub fn clear(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        let lines = std::mem::take(&mut self.items);
+        self.history.record(CartOp::Clear { lines });
+    }
+
+    /// Removes the line for `ean` from the `country` storefront.
+    pub fn remove(&mut self, country: Country, ean: &str) -> Option<CartLine> {
+        let index = self
+            .items
+            .iter()
+            .position(|line| line.product.ean == ean && line.product.country == country)?;
+        self.remove_at(index)
+    }
+
+    /// Changes recorded since the cart was loaded, for undo and redo.
+    pub fn history(&self) -> &CartHistory {
+        &self.history
+    }
+
+    /// Reverts the last change and returns its description.
+    pub fn undo(&mut self) -> Option<String> {
+        self.history.undo(&mut self.items)
+    }
+
+    /// Applies the last undone change again and returns its description.
+    pub fn redo(&mut self) -> Option<String> {
+        self.history.redo(&mut self.items)
+    }
+
+    /// Sum of all lines per currency, in [`Currency::ALL`] order; currencies
+    /// without any line are left out.
+    pub fn totals(&self) -> Vec<(Currency, Money)> {
+        Currency::ALL
+            .iter()
+            .filter_map(|&currency| {
+                let mut lines = self
+                    .items
+                    .iter()
+                    .filter(|line| line.product.currency() == currency)
+                    .peekable();
+                lines.peek()?;
+                let total: Money = lines.map(CartLine::total).sum();
+                Some((currency, total))
+            })
+            .collect()
+    }
+
+    /// How much of the cart's budget is used, counting `extra` (amounts
+    /// about to be added) as well; `None` if the cart has no budget.
+    ///
+    /// Lines count at the rate they were added at, `extra` and the budget's
+    /// own currency at `rates`.
+    pub fn budget_status(
+        &self,
+        rates: &ExchangeRates,
+        rounding: PlnRounding,
+        extra: impl IntoIterator<Item = (Money, Currency)>,
+    ) -> Option<BudgetStatus> {
+        let budget = self.budget?;
+        let amounts = self
+            .items
+            .iter()
+            .map(|line| (line.total(), line.product.currency(), line.exchange_rate))
+            .chain(
+                extra
+                    .into_iter()
+                    .map(|(amount, currency)| (amount, currency, rates.get(currency))),
+            );
+        Some(budget.status(amounts, rates, rounding))
+    }
+
+    /// Sum of all lines converted to PLN at the rate each was added at,
+    /// rounded as configured in `rounding`.
+    pub fn total_pln(&self, rounding: PlnRounding) -> Money {
+        pricing::sum_pln(
+            self.items
+                .iter()
+                .map(|line| (line.total(), line.exchange_rate)),
+            rounding,
+        )
+    }
+}
+
+/// Directory archived carts are written to.
+pub fn archive_dir() -> PathBuf {
+    storage::data_dir().join("archive")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::test_product;
+
+    #[test]
+    fn lines_keep_the_rate_they_were_added_at() {
+        let mut cart = Cart::new();
+        cart.add(test_product("4058172936760", 2), Rate::from_f64(4.0));
+        cart.add(test_product("4058172936760", 1), Rate::from_f64(5.0));
+        cart.add(test_product("4066447000001", 1), Rate::from_f64(5.0));
+        assert_eq!(cart.items()[0].exchange_rate, Rate::from_f64(4.0));
+        // 3 x 1.95 EUR at 4.00 and 1.95 EUR at 5.00.
+        assert_eq!(
+            cart.total_pln(PlnRounding::default()),
+            Money::from_minor(2340 + 975)
+        );
+    }
+
+    #[test]
+    fn assign_caps_at_the_unassigned_quantity() {
+        let mut cart = Cart::new();
+        cart.add(test_product("4058172936760", 3), Rate::ZERO);
+        assert!(cart.assign(0, "Ania", 5));
+        assert_eq!(cart.items()[0].share_of("Ania"), 3);
+        assert!(!cart.assign(0, "Ola", 1));
+        assert!(cart.assign(0, "Ania", 0));
+        assert!(cart.items()[0].shares.is_empty());
+    }
+
+    #[test]
+    fn assign_on_an_overassigned_line_does_not_panic() {
+        let mut cart = Cart::new();
+        cart.add(test_product("4058172936760", 2), Rate::ZERO);
+        cart.assign(0, "Ania", 2);
+        // As loaded from a file written before negative quantities were
+        // rejected.
+        cart.items[0].product.quantity = -3;
+        assert!(!cart.assign(0, "Ola", 1));
+        assert!(cart.assign(0, "Ania", 1));
+        assert_eq!(cart.items()[0].share_of("Ania"), 0);
+    }
+}
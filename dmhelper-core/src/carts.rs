@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    cart::Cart,
+    error::{Error, Result},
+    storage,
+};
+
+/// All named carts plus the one currently being edited.
+///
+/// There is always at least one cart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CartList {
+    carts: Vec<Cart>,
+    active: usize,
+}
+
+impl Default for CartList {
+    fn default() -> Self {
+        Self {
+            carts: vec![Cart::new()],
+            active: 0,
+        }
+    }
+}
+
+impl CartList {
+    /// Loads the carts saved with [`CartList::save`].
+    ///
+    /// When nothing was saved yet the single cart older versions kept in
+    /// `cart.json` next to `path` is picked up instead.
+    pub fn load(path: &Path) -> Result<Self> {
+        if let Some(mut list) = storage::load_json::<CartList>(path)? {
+            if list.carts.is_empty() {
+                list.carts.push(Cart::new());
+            }
+            list.active = list.active.min(list.carts.len() - 1);
+            return Ok(list);
+        }
+        let legacy_path = path.with_file_name("cart.json");
+        Ok(match storage::load_json::<Cart>(&legacy_path)? {
+            Some(cart) => Self {
+                carts: vec![cart],
+                active: 0,
+            },
+            None => Self::default(),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        storage::save_json(path, self)
+    }
+
+    pub fn carts(&self) -> &[Cart] {
+        &self.carts
+    }
+
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    pub fn active(&self) -> &Cart {
+        &self.carts[self.active]
+    }
+
+    pub fn active_mut(&mut self) -> &mut Cart {
+        &mut self.carts[self.active]
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Cart> {
+        self.carts.get_mut(index)
+    }
+
+    pub fn set_active(&mut self, index: usize) {
+        if index < self.carts.len() {
+            self.active = index;
+        }
+    }
+
+    /// Index of the first cart called `name`, ignoring case.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.carts
+            .iter()
+            .position(|cart| cart.name.to_lowercase() == name.to_lowercase())
+    }
+
+    /// Renames the cart at `index` to `name` with surrounding whitespace
+    /// trimmed, returning whether the name changed.
+    ///
+    /// Empty names and names another cart already has (ignoring case, as in
+    /// [`CartList::find`]) are rejected.
+    pub fn rename(&mut self, index: usize, name: &str) -> Result<bool> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::Parse("pusta nazwa koszyka".to_string()));
+        }
+        if self.find(name).is_some_and(|other| other != index) {
+            return Err(Error::Parse(format!("koszyk {} już istnieje", name)));
+        }
+        let Some(cart) = self.carts.get_mut(index) else {
+            return Ok(false);
+        };
+        if cart.name == name {
+            return Ok(false);
+        }
+        cart.name = name.to_string();
+        Ok(true)
+    }
+
+    /// Adds an empty cart called `name`, makes it active and returns its
+    /// index.
+    pub fn create(&mut self, name: &str) -> usize {
+        self.carts.push(Cart::named(name));
+        self.active = self.carts.len() - 1;
+        self.active
+    }
+
+    /// Replaces the cart at `index` with an empty one of the same name,
+    /// pinned rates and budget, returning the old cart.
+    pub fn restart(&mut self, index: usize) -> Option<Cart> {
+        let cart = self.carts.get_mut(index)?;
+        let mut fresh = Cart::named(&cart.name);
+        fresh.exchange_rates = cart.exchange_rates.clone();
+        fresh.budget = cart.budget;
+        Some(std::mem::replace(cart, fresh))
+    }
+
+    /// Removes the cart at `index`; the last remaining cart cannot be removed.
+    pub fn remove(&mut self, index: usize) -> Option<Cart> {
+        if self.carts.len() <= 1 || index >= self.carts.len() {
+            return None;
+        }
+        let cart = self.carts.remove(index);
+        if self.active > index || self.active == self.carts.len() {
+            self.active -= 1;
+        }
+        Some(cart)
+    }
+
+    /// Copies line `line` of cart `from` into cart `to` with its shares,
+    /// merging it with a line for the same product there.
+    pub fn copy_line(&mut self, from: usize, line: usize, to: usize) -> bool {
+        if from == to {
+            return false;
+        }
+        let Some(copied) = self
+            .carts
+            .get(from)
+            .and_then(|cart| cart.items().get(line))
+            .cloned()
+        else {
+            return false;
+        };
+        match self.carts.get_mut(to) {
+            Some(cart) => cart.add_line(copied),
+            None => false,
+        }
+    }
+
+    /// Moves line `line` of cart `from` into cart `to`, merging it with a
+    /// line for the same product there.
+    pub fn move_line(&mut self, from: usize, line: usize, to: usize) -> bool {
+        if !self.copy_line(from, line, to) {
+            return false;
+        }
+        self.carts[from].remove_at(line).is_some()
+    }
+}
+
+/// File the carts are autosaved to.
+pub fn carts_path() -> PathBuf {
+    storage::data_dir().join("carts.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rename_rejects_empty_and_taken_names() {
+        let mut carts = CartList::default();
+        carts.create("Wiedeń");
+        assert!(carts.rename(0, "  ").is_err());
+        assert!(carts.rename(0, "wiedeń").is_err());
+        assert!(carts.rename(1, "wiedeń").unwrap());
+        assert_eq!(carts.carts()[1].name, "wiedeń");
+        assert!(!carts.rename(1, " wiedeń ").unwrap());
+        assert!(carts.rename(0, " Praga ").unwrap());
+        assert_eq!(carts.carts()[0].name, "Praga");
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Currency a dm storefront charges in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    Eur,
+    Czk,
+    Huf,
+    Ron,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 4] = [Currency::Eur, Currency::Czk, Currency::Huf, Currency::Ron];
+
+    /// ISO 4217 code, e.g. `"EUR"`.
+    pub fn code(self) -> &'static str {
+        match self {
+            Currency::Eur => "EUR",
+            Currency::Czk => "CZK",
+            Currency::Huf => "HUF",
+            Currency::Ron => "RON",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Currency> {
+        Currency::ALL
+            .iter()
+            .copied()
+            .find(|currency| currency.code().eq_ignore_ascii_case(code))
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// A dm storefront country.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Country {
+    #[default]
+    De,
+    At,
+    Cz,
+    Sk,
+    Hu,
+    Si,
+    Hr,
+    Ro,
+    Bg,
+}
+
+impl Country {
+    pub const ALL: [Country; 9] = [
+        Country::De,
+        Country::At,
+        Country::Cz,
+        Country::Sk,
+        Country::Hu,
+        Country::Si,
+        Country::Hr,
+        Country::Ro,
+        Country::Bg,
+    ];
+
+    /// Country segment used in the dm product API, e.g. `"DE"`.
+    pub fn code(self) -> &'static str {
+        match self {
+            Country::De => "DE",
+            Country::At => "AT",
+            Country::Cz => "CZ",
+            Country::Sk => "SK",
+            Country::Hu => "HU",
+            Country::Si => "SI",
+            Country::Hr => "HR",
+            Country::Ro => "RO",
+            Country::Bg => "BG",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Country> {
+        Country::ALL
+            .iter()
+            .copied()
+            .find(|country| country.code().eq_ignore_ascii_case(code))
+    }
+
+    /// Currency prices from this storefront are in.
+    pub fn currency(self) -> Currency {
+        match self {
+            Country::Cz => Currency::Czk,
+            Country::Hu => Currency::Huf,
+            Country::Ro => Currency::Ron,
+            // Bulgaria moved from BGN to the euro on 2026-01-01.
+            Country::De | Country::At | Country::Sk | Country::Si | Country::Hr | Country::Bg => {
+                Currency::Eur
+            }
+        }
+    }
+}
+
+impl fmt::Display for Country {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for Country {
+    type Err = String;
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        Country::from_code(code).ok_or_else(|| format!("Nieznany kraj: {}", code))
+    }
+}
@@ -0,0 +1,97 @@
+use std::{fmt, io};
+
+/// Everything that can go wrong in DMHelper, with messages meant for users.
+#[derive(Debug)]
+pub enum Error {
+    /// The storefront has no product with this EAN.
+    NotFound(String),
+    /// There is no cart with this name.
+    CartNotFound(String),
+    /// The request never got a response (offline, DNS, timeout...).
+    Network(reqwest::Error),
+    /// The server answered with a non-success status.
+    HttpStatus { status: u16, url: String },
+    /// A response or saved file could not be understood.
+    Parse(String),
+    /// A product image could not be downloaded or decoded.
+    Image(String),
+    /// A summary could not be printed to PDF.
+    Pdf(String),
+    /// Reading or writing a local file failed.
+    Io(io::Error),
+    /// A background task stopped without delivering its result.
+    Interrupted,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound(ean) => write!(f, "Nie znaleziono produktu {}", ean),
+            Error::CartNotFound(name) => write!(f, "Nie ma koszyka {}", name),
+            Error::Network(e) => write!(f, "Błąd sieci: {}", e),
+            Error::HttpStatus { status, url } => {
+                write!(f, "Serwer zwrócił kod {} dla {}", status, url)
+            }
+            Error::Parse(message) => write!(f, "Niepoprawne dane: {}", message),
+            Error::Image(message) => write!(f, "Błąd obrazka: {}", message),
+            Error::Pdf(message) => write!(f, "Nie udało się utworzyć PDF: {}", message),
+            Error::Io(e) => write!(f, "Błąd pliku: {}", e),
+            Error::Interrupted => write!(f, "Zadanie w tle zostało przerwane"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Network(e) => Some(e),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            Error::Parse(e.to_string())
+        } else {
+            Error::Network(e)
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Parse(e.to_string())
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(e: image::ImageError) -> Self {
+        Error::Image(e.to_string())
+    }
+}
+
+/// Returns an [`Error::HttpStatus`] unless `response` was successful.
+pub(crate) fn check_status(
+    response: reqwest::blocking::Response,
+) -> Result<reqwest::blocking::Response> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        Err(Error::HttpStatus {
+            status: status.as_u16(),
+            url: response.url().to_string(),
+        })
+    }
+}
@@ -0,0 +1,535 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::Local;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    cart::{Cart, CartLine},
+    error::{Error, Result},
+    money::{Money, Rate},
+    pricing::{self, PlnRounding},
+    storage,
+};
+
+/// Field separator Polish spreadsheets expect, since `,` is the decimal
+/// separator there.
+pub const CSV_DELIMITER: char = ';';
+
+/// Marks the file as UTF-8 so spreadsheets keep Polish characters intact.
+const UTF8_BOM: &str = "\u{feff}";
+
+/// Browsers and converters tried, in order, to print a summary to PDF.
+const PDF_CONVERTERS: [&str; 6] = [
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "msedge",
+    "wkhtmltopdf",
+];
+
+const SUMMARY_STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border-bottom: 1px solid #ccc; padding: 6px; text-align: left; vertical-align: middle; }
+td.number, th.number { text-align: right; white-space: nowrap; }
+td.image { width: 90px; }
+td.image img { max-width: 80px; max-height: 80px; }
+.note { color: #555; font-size: 0.9em; }
+tfoot td { font-weight: bold; border-bottom: none; }
+tr { page-break-inside: avoid; }
+@page { margin: 1.5cm; }
+@media print { body { margin: 0; } }
+";
+
+/// Columns of a cart line in the CSV exports, see [`line_fields`].
+const LINE_COLUMNS: [&str; 14] = [
+    "EAN",
+    "Marka",
+    "Nazwa",
+    "Zawartość",
+    "Ilość",
+    "Cena jednostkowa",
+    "Waluta",
+    "Wartość",
+    "Kurs",
+    "Wartość PLN",
+    "Cena bazowa",
+    "Notatka",
+    "Tagi",
+    "Opis",
+];
+
+/// Positions of the columns total rows fill in.
+const NAME_COLUMN: usize = 2;
+const CURRENCY_COLUMN: usize = 6;
+const TOTAL_COLUMN: usize = 7;
+const TOTAL_PLN_COLUMN: usize = 9;
+
+/// Renders `cart` as CSV: one row per line with its EAN, brand, name, net
+/// content, quantity, unit price, currency, line total, the rate used, the
+/// line total in PLN, base price, note, tags and description, followed by a
+/// total row per currency and the PLN total.
+///
+/// Amounts use a decimal comma and rows end with `\r\n`.
+pub fn cart_csv(cart: &Cart, rounding: PlnRounding) -> String {
+    let mut csv = String::new();
+    push_row(&mut csv, &LINE_COLUMNS);
+    for line in cart.items() {
+        let fields = line_fields(line, line.product.quantity, rounding);
+        push_row(&mut csv, &fields);
+    }
+    for (currency, total) in cart.totals() {
+        push_row(
+            &mut csv,
+            &total_row(&[
+                (NAME_COLUMN, "Suma"),
+                (CURRENCY_COLUMN, currency.code()),
+                (TOTAL_COLUMN, &decimal(total)),
+            ]),
+        );
+    }
+    push_row(
+        &mut csv,
+        &total_row(&[
+            (NAME_COLUMN, "Suma PLN"),
+            (TOTAL_PLN_COLUMN, &decimal(cart.total_pln(rounding))),
+        ]),
+    );
+    csv
+}
+
+/// Renders the per-person breakdown of `cart` as CSV: for every person
+/// (and the unassigned rest) their part of each line followed by a total
+/// row per currency and in PLN, in the same format as [`cart_csv`] with the
+/// person in the first column.
+pub fn buyers_csv(cart: &Cart, rounding: PlnRounding) -> String {
+    let mut csv = String::new();
+    let mut header = vec!["Osoba"];
+    header.extend(LINE_COLUMNS);
+    push_row(&mut csv, &header);
+    for buyer_total in cart.buyer_totals(rounding) {
+        let buyer = buyer_label(buyer_total.buyer.as_deref());
+        for part in cart.buyer_lines(buyer_total.buyer.as_deref()) {
+            let mut fields = vec![buyer.to_string()];
+            fields.extend(line_fields(part.line, part.quantity, rounding));
+            push_row(&mut csv, &fields);
+        }
+        for (currency, total) in &buyer_total.totals {
+            let mut fields = vec![buyer.to_string()];
+            fields.extend(total_row(&[
+                (NAME_COLUMN, "Suma"),
+                (CURRENCY_COLUMN, currency.code()),
+                (TOTAL_COLUMN, &decimal(*total)),
+            ]));
+            push_row(&mut csv, &fields);
+        }
+        let mut fields = vec![buyer.to_string()];
+        fields.extend(total_row(&[
+            (NAME_COLUMN, "Suma PLN"),
+            (TOTAL_PLN_COLUMN, &decimal(buyer_total.total_pln)),
+        ]));
+        push_row(&mut csv, &fields);
+    }
+    csv
+}
+
+/// The [`LINE_COLUMNS`] of `quantity` items of `line`.
+fn line_fields(line: &CartLine, quantity: i32, rounding: PlnRounding) -> Vec<String> {
+    let product = &line.product;
+    let rate = line.exchange_rate;
+    let total = pricing::line_total(product.price, quantity);
+    vec![
+        product.ean.clone(),
+        product.brand.clone().unwrap_or_default(),
+        product.name.clone(),
+        product.size.clone().unwrap_or_default(),
+        quantity.to_string(),
+        decimal(product.price),
+        product.currency().code().to_string(),
+        decimal(total),
+        decimal_rate(rate),
+        decimal(pricing::to_pln(total, rate, rounding.mode)),
+        product
+            .unit_price
+            .as_ref()
+            .map(|unit_price| format!("{} / {}", decimal(unit_price.price), unit_price.unit))
+            .unwrap_or_default(),
+        line.note.clone(),
+        tags_label(&line.tags),
+        product.description.clone(),
+    ]
+}
+
+/// A row of [`LINE_COLUMNS`] with only `cells` filled in.
+fn total_row(cells: &[(usize, &str)]) -> Vec<String> {
+    let mut row = vec![String::new(); LINE_COLUMNS.len()];
+    for (column, value) in cells {
+        row[*column] = value.to_string();
+    }
+    row
+}
+
+/// How a line's tags are shown in exports.
+pub fn tags_label(tags: &[String]) -> String {
+    tags.join(", ")
+}
+
+/// How a buyer is shown in exports; `None` is the unassigned rest.
+pub fn buyer_label(buyer: Option<&str>) -> &str {
+    buyer.unwrap_or("Nieprzypisane")
+}
+
+/// Writes `csv` to `path` with a byte order mark so it opens correctly in
+/// Excel.
+pub fn save_csv(path: &Path, csv: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, format!("{}{}", UTF8_BOM, csv))?;
+    Ok(())
+}
+
+/// Renders `cart` as a printable HTML order summary with every product's
+/// image, brand, name, net content, base price, note, tags, EAN, quantity
+/// and prices in its currency and in PLN, followed by the totals and, if
+/// lines are assigned to people, what each person owes.
+///
+/// Images are embedded as data URIs, so the file needs nothing else to
+/// display.
+pub fn cart_summary_html(cart: &Cart, rounding: PlnRounding) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"pl\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape(&cart.name)));
+    html.push_str(&format!(
+        "<style>\n{}</style>\n</head>\n<body>\n",
+        SUMMARY_STYLE
+    ));
+    html.push_str(&format!("<h1>{}</h1>\n", escape(&cart.name)));
+    html.push_str(&format!(
+        "<p>Stan na {}</p>\n",
+        Local::now().format("%d.%m.%Y %H:%M")
+    ));
+    html.push_str(
+        "<table>\n<thead><tr><th></th><th>Produkt</th><th>EAN</th>\
+         <th class=\"number\">Ilość</th><th class=\"number\">Cena</th>\
+         <th class=\"number\">Wartość</th><th class=\"number\">Wartość PLN</th>\
+         </tr></thead>\n<tbody>\n",
+    );
+    for line in cart.items() {
+        let product = &line.product;
+        let currency = product.currency();
+        let image = match product.image.as_deref().and_then(data_uri) {
+            Some(uri) => format!("<img src=\"{}\" alt=\"\">", uri),
+            None => String::new(),
+        };
+        html.push_str(&format!(
+            "<tr><td class=\"image\">{}</td><td>{}</td><td>{}</td>\
+             <td class=\"number\">{}</td><td class=\"number\">{} {}</td>\
+             <td class=\"number\">{} {}</td><td class=\"number\">{} PLN</td></tr>\n",
+            image,
+            product_cell(line),
+            escape(&product.ean),
+            product.quantity,
+            decimal(product.price),
+            currency,
+            decimal(line.total()),
+            currency,
+            decimal(pricing::to_pln(
+                line.total(),
+                line.exchange_rate,
+                rounding.mode
+            )),
+        ));
+    }
+    html.push_str("</tbody>\n<tfoot>\n");
+    for (currency, total) in cart.totals() {
+        html.push_str(&format!(
+            "<tr><td></td><td colspan=\"4\">Suma {}</td>\
+             <td class=\"number\">{} {}</td><td></td></tr>\n",
+            currency,
+            decimal(total),
+            currency,
+        ));
+    }
+    html.push_str(&format!(
+        "<tr><td></td><td colspan=\"5\">Do zapłaty</td>\
+         <td class=\"number\">{} PLN</td></tr>\n",
+        decimal(cart.total_pln(rounding))
+    ));
+    html.push_str("</tfoot>\n</table>\n");
+    if !cart.buyers().is_empty() {
+        push_buyers_html(&mut html, cart, rounding);
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Appends a section per person listing their part of each line and what
+/// they owe.
+fn push_buyers_html(html: &mut String, cart: &Cart, rounding: PlnRounding) {
+    html.push_str("<h2>Podział na osoby</h2>\n");
+    for buyer_total in cart.buyer_totals(rounding) {
+        let buyer = buyer_total.buyer.as_deref();
+        html.push_str(&format!(
+            "<h3>{}</h3>\n<table>\n<tbody>\n",
+            escape(buyer_label(buyer))
+        ));
+        for part in cart.buyer_lines(buyer) {
+            let product = &part.line.product;
+            let currency = product.currency();
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td class=\"number\">{}</td>\
+                 <td class=\"number\">{} {}</td><td class=\"number\">{} PLN</td></tr>\n",
+                product_cell(part.line),
+                escape(&product.ean),
+                part.quantity,
+                decimal(part.total()),
+                currency,
+                decimal(pricing::to_pln(
+                    part.total(),
+                    part.line.exchange_rate,
+                    rounding.mode
+                )),
+            ));
+        }
+        let totals: Vec<String> = buyer_total
+            .totals
+            .iter()
+            .map(|(currency, total)| format!("{} {}", decimal(*total), currency))
+            .collect();
+        html.push_str(&format!(
+            "</tbody>\n<tfoot><tr><td colspan=\"3\">Razem</td>\
+             <td class=\"number\">{}</td><td class=\"number\">{} PLN</td></tr></tfoot>\n\
+             </table>\n",
+            totals.join(" + "),
+            decimal(buyer_total.total_pln)
+        ));
+    }
+}
+
+/// The product's brand and name with its net content, base price and the
+/// line's note and tags under it.
+fn product_cell(line: &CartLine) -> String {
+    let product = &line.product;
+    let mut cell = match &product.brand {
+        Some(brand) => format!("<b>{}</b> {}", escape(brand), escape(&product.name)),
+        None => escape(&product.name),
+    };
+    let details: Vec<String> = product
+        .size
+        .iter()
+        .cloned()
+        .chain(product.unit_price.as_ref().map(|unit_price| {
+            format!(
+                "{} {} / {}",
+                decimal(unit_price.price),
+                product.currency(),
+                unit_price.unit
+            )
+        }))
+        .collect();
+    if !details.is_empty() {
+        cell.push_str(&format!(
+            "<div class=\"note\">{}</div>",
+            escape(&details.join(", "))
+        ));
+    }
+    if !line.note.is_empty() {
+        cell.push_str(&format!("<div class=\"note\">{}</div>", escape(&line.note)));
+    }
+    if !line.tags.is_empty() {
+        cell.push_str(&format!(
+            "<div class=\"note\">#{}</div>",
+            escape(&line.tags.join(" #"))
+        ));
+    }
+    cell
+}
+
+/// Writes `html` to `path`.
+pub fn save_html(path: &Path, html: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, html)?;
+    Ok(())
+}
+
+/// Prints the HTML file at `html_path` to `pdf_path` with the first
+/// Chromium-based browser or `wkhtmltopdf` found on the `PATH`.
+pub fn html_to_pdf(html_path: &Path, pdf_path: &Path) -> Result<()> {
+    let html_path = fs::canonicalize(html_path)?;
+    if let Some(parent) = pdf_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    for converter in PDF_CONVERTERS {
+        let mut command = Command::new(converter);
+        if converter == "wkhtmltopdf" {
+            command.arg("--quiet").arg(&html_path).arg(pdf_path);
+        } else {
+            command
+                .arg("--headless")
+                .arg("--disable-gpu")
+                .arg("--no-pdf-header-footer")
+                .arg(format!("--print-to-pdf={}", pdf_path.display()))
+                .arg(&html_path);
+        }
+        match command.output() {
+            Ok(output) if output.status.success() => return Ok(()),
+            Ok(output) => {
+                return Err(Error::Pdf(format!(
+                    "{} zakończył się błędem: {}",
+                    converter,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(Error::Pdf(
+        "nie znaleziono Chromium, Chrome, Edge ani wkhtmltopdf".to_string(),
+    ))
+}
+
+/// Directory exported files are written to unless told otherwise.
+pub fn exports_dir() -> PathBuf {
+    storage::data_dir().join("exports")
+}
+
+/// Default path for an export of `cart` with extension `extension`.
+pub fn export_path(cart: &Cart, extension: &str) -> PathBuf {
+    exports_dir().join(format!("{}.{}", cart.file_stem(), extension))
+}
+
+fn decimal(amount: Money) -> String {
+    amount.to_string().replace('.', ",")
+}
+
+fn decimal_rate(rate: Rate) -> String {
+    rate.to_string().replace('.', ",")
+}
+
+/// `bytes` as a `data:` URI, or `None` if they are not a known image format.
+fn data_uri(bytes: &[u8]) -> Option<String> {
+    let format = image::guess_format(bytes).ok()?;
+    Some(format!(
+        "data:{};base64,{}",
+        format.to_mime_type(),
+        STANDARD.encode(bytes)
+    ))
+}
+
+/// Escapes text for use in HTML element content and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn push_row(csv: &mut String, fields: &[impl AsRef<str>]) {
+    for (index, field) in fields.iter().enumerate() {
+        if index > 0 {
+            csv.push(CSV_DELIMITER);
+        }
+        push_field(csv, field.as_ref());
+    }
+    csv.push_str("\r\n");
+}
+
+/// Quotes `field` if it contains the delimiter, a quote or a line break.
+fn push_field(csv: &mut String, field: &str) {
+    if field.contains([CSV_DELIMITER, '"', '\n', '\r']) {
+        csv.push('"');
+        csv.push_str(&field.replace('"', "\"\""));
+        csv.push('"');
+    } else {
+        csv.push_str(field);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::test_product;
+
+    fn cart() -> Cart {
+        let mut cart = Cart::new();
+        cart.add(test_product("1111111111111", 2), Rate::from_f64(4.3));
+        cart.add(test_product("2222222222222", 1), Rate::from_f64(4.3));
+        cart.set_note(1, "tylko; \"w promocji\"");
+        cart
+    }
+
+    fn rows(csv: &str) -> Vec<&str> {
+        csv.split_terminator("\r\n").collect()
+    }
+
+    #[test]
+    fn cart_csv_uses_semicolons_and_decimal_commas() {
+        let csv = cart_csv(&cart(), PlnRounding::default());
+        assert_eq!(
+            rows(&csv),
+            [
+                "EAN;Marka;Nazwa;Zawartość;Ilość;Cena jednostkowa;Waluta;Wartość;Kurs;\
+                 Wartość PLN;Cena bazowa;Notatka;Tagi;Opis",
+                "1111111111111;;Produkt 1111111111111;;2;1,95;EUR;3,90;4,3000;16,77;;;;",
+                "2222222222222;;Produkt 2222222222222;;1;1,95;EUR;1,95;4,3000;8,39;;\
+                 \"tylko; \"\"w promocji\"\"\";;",
+                ";;Suma;;;;EUR;5,85;;;;;;",
+                ";;Suma PLN;;;;;;;25,16;;;;",
+            ]
+        );
+    }
+
+    #[test]
+    fn buyers_csv_puts_the_person_first_and_totals_each_person() {
+        let mut cart = cart();
+        cart.assign(0, "Ania", 1);
+        let csv = buyers_csv(&cart, PlnRounding::default());
+        let rows = rows(&csv);
+        assert!(rows[0].starts_with("Osoba;EAN;"));
+        assert_eq!(
+            rows[1..4],
+            [
+                "Ania;1111111111111;;Produkt 1111111111111;;1;1,95;EUR;1,95;4,3000;8,39;;;;",
+                "Ania;;;Suma;;;;EUR;1,95;;;;;;",
+                "Ania;;;Suma PLN;;;;;;;8,39;;;;",
+            ]
+        );
+        assert!(rows[4].starts_with("Nieprzypisane;1111111111111;"));
+        assert_eq!(
+            rows.last(),
+            Some(&"Nieprzypisane;;;Suma PLN;;;;;;;16,77;;;;")
+        );
+    }
+
+    #[test]
+    fn fields_are_quoted_only_when_needed() {
+        let mut csv = String::new();
+        push_row(&mut csv, &["a", "b;c", "d\"e", "f\ng", "1,5"]);
+        assert_eq!(csv, "a;\"b;c\";\"d\"\"e\";\"f\ng\";1,5\r\n");
+    }
+
+    #[test]
+    fn escape_covers_html_special_characters() {
+        assert_eq!(
+            escape("<b>Tom & \"Jerry's\"</b>"),
+            "&lt;b&gt;Tom &amp; &quot;Jerry&#39;s&quot;&lt;/b&gt;"
+        );
+        assert_eq!(escape("Zahnpasta"), "Zahnpasta");
+    }
+}
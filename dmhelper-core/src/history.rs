@@ -0,0 +1,215 @@
+use crate::cart::CartLine;
+
+/// How many changes are kept for undo.
+pub const MAX_UNDO: usize = 100;
+
+/// A change to the lines of a cart, with enough data to undo and redo it.
+#[derive(Debug, Clone)]
+pub enum CartOp {
+    /// A new line was added at `index`.
+    Add { index: usize, line: CartLine },
+    /// A product was added to the existing line at `index`.
+    Merge {
+        index: usize,
+        before: CartLine,
+        after: CartLine,
+    },
+    /// The line at `index` was edited, e.g. its quantity.
+    Edit {
+        index: usize,
+        before: CartLine,
+        after: CartLine,
+    },
+    /// The line at `index` was removed.
+    Remove { index: usize, line: CartLine },
+    /// Every line was removed.
+    Clear { lines: Vec<CartLine> },
+}
+
+impl CartOp {
+    /// Short description for undo/redo tooltips.
+    pub fn label(&self) -> String {
+        match self {
+            CartOp::Add { line, .. } => format!("dodanie: {}", line.product.name),
+            CartOp::Merge { after, .. } => format!("dołożenie do: {}", after.product.name),
+            CartOp::Edit { after, .. } => format!("zmiana: {}", after.product.name),
+            CartOp::Remove { line, .. } => format!("usunięcie: {}", line.product.name),
+            CartOp::Clear { .. } => "wyczyszczenie koszyka".to_string(),
+        }
+    }
+
+    fn apply(&self, items: &mut Vec<CartLine>) {
+        match self {
+            CartOp::Add { index, line } => items.insert((*index).min(items.len()), line.clone()),
+            CartOp::Merge { index, after, .. } | CartOp::Edit { index, after, .. } => {
+                if let Some(line) = items.get_mut(*index) {
+                    *line = after.clone();
+                }
+            }
+            CartOp::Remove { index, .. } => {
+                if *index < items.len() {
+                    items.remove(*index);
+                }
+            }
+            CartOp::Clear { .. } => items.clear(),
+        }
+    }
+
+    fn revert(&self, items: &mut Vec<CartLine>) {
+        match self {
+            CartOp::Add { index, .. } => {
+                if *index < items.len() {
+                    items.remove(*index);
+                }
+            }
+            CartOp::Merge { index, before, .. } | CartOp::Edit { index, before, .. } => {
+                if let Some(line) = items.get_mut(*index) {
+                    *line = before.clone();
+                }
+            }
+            CartOp::Remove { index, line } => items.insert((*index).min(items.len()), line.clone()),
+            CartOp::Clear { lines } => *items = lines.clone(),
+        }
+    }
+}
+
+/// Undo and redo stacks of a cart's changes.
+///
+/// Recording a new change drops whatever could be redone.
+#[derive(Debug, Clone, Default)]
+pub struct CartHistory {
+    undo: Vec<CartOp>,
+    redo: Vec<CartOp>,
+}
+
+impl CartHistory {
+    pub(crate) fn record(&mut self, op: CartOp) {
+        self.redo.clear();
+        self.undo.push(op);
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+        }
+    }
+
+    /// The change [`CartHistory::undo`] would revert.
+    pub fn next_undo(&self) -> Option<&CartOp> {
+        self.undo.last()
+    }
+
+    /// The change [`CartHistory::redo`] would apply again.
+    pub fn next_redo(&self) -> Option<&CartOp> {
+        self.redo.last()
+    }
+
+    /// Reverts the last change to `items` and returns its description.
+    pub(crate) fn undo(&mut self, items: &mut Vec<CartLine>) -> Option<String> {
+        let op = self.undo.pop()?;
+        op.revert(items);
+        let label = op.label();
+        self.redo.push(op);
+        Some(label)
+    }
+
+    /// Applies the last undone change to `items` again and returns its
+    /// description.
+    pub(crate) fn redo(&mut self, items: &mut Vec<CartLine>) -> Option<String> {
+        let op = self.redo.pop()?;
+        op.apply(items);
+        let label = op.label();
+        self.undo.push(op);
+        Some(label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cart::Cart, money::Rate, product::test_product};
+
+    fn quantities(cart: &Cart) -> Vec<(String, i32)> {
+        cart.items()
+            .iter()
+            .map(|line| (line.product.ean.clone(), line.product.quantity))
+            .collect()
+    }
+
+    fn q(ean: &str, quantity: i32) -> (String, i32) {
+        (ean.to_string(), quantity)
+    }
+
+    #[test]
+    fn undo_and_redo_every_kind_of_change() {
+        let mut cart = Cart::new();
+        cart.add(test_product("1111111111111", 1), Rate::ZERO);
+        cart.add(test_product("2222222222222", 2), Rate::ZERO);
+        cart.add(test_product("1111111111111", 3), Rate::ZERO);
+        cart.set_quantity(1, 5);
+        cart.remove_at(0);
+        cart.clear();
+        let steps = [
+            vec![],
+            vec![q("2222222222222", 5)],
+            vec![q("1111111111111", 4), q("2222222222222", 5)],
+            vec![q("1111111111111", 4), q("2222222222222", 2)],
+            vec![q("1111111111111", 1), q("2222222222222", 2)],
+            vec![q("1111111111111", 1)],
+            vec![],
+        ];
+
+        assert_eq!(quantities(&cart), steps[0]);
+        for expected in &steps[1..] {
+            assert!(cart.undo().is_some());
+            assert_eq!(&quantities(&cart), expected);
+        }
+        assert!(cart.undo().is_none());
+
+        for expected in steps.iter().rev().skip(1) {
+            assert!(cart.redo().is_some());
+            assert_eq!(&quantities(&cart), expected);
+        }
+        assert!(cart.redo().is_none());
+    }
+
+    #[test]
+    fn recording_a_change_drops_the_redo_stack() {
+        let mut cart = Cart::new();
+        cart.add(test_product("1111111111111", 1), Rate::ZERO);
+        cart.add(test_product("2222222222222", 1), Rate::ZERO);
+        cart.undo();
+        assert!(cart.history().next_redo().is_some());
+        cart.set_quantity(0, 2);
+        assert!(cart.history().next_redo().is_none());
+        assert!(cart.redo().is_none());
+        assert_eq!(quantities(&cart), vec![q("1111111111111", 2)]);
+    }
+
+    #[test]
+    fn unchanged_edits_are_not_recorded() {
+        let mut cart = Cart::new();
+        cart.add(test_product("1111111111111", 2), Rate::ZERO);
+        assert!(!cart.set_quantity(0, 2));
+        assert!(matches!(
+            cart.history().next_undo(),
+            Some(CartOp::Add { .. })
+        ));
+        cart.clear();
+        cart.clear();
+        assert!(cart.undo().is_some());
+        assert_eq!(quantities(&cart), vec![q("1111111111111", 2)]);
+    }
+
+    #[test]
+    fn keeps_at_most_max_undo_changes() {
+        let mut cart = Cart::new();
+        cart.add(test_product("1111111111111", 1), Rate::ZERO);
+        for quantity in 2..MAX_UNDO as i32 + 10 {
+            cart.set_quantity(0, quantity);
+        }
+        let mut undone = 0;
+        while cart.undo().is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_UNDO);
+        assert_eq!(cart.items().len(), 1);
+    }
+}
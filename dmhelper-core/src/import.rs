@@ -0,0 +1,229 @@
+use crate::{
+    cache::ProductCache,
+    cart::Cart,
+    country::Country,
+    error::{Error, Result},
+    lookup,
+    pricing::ExchangeRates,
+    product::Product,
+    source::ProductSource,
+};
+
+/// One EAN to import with the quantity wanted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportEntry {
+    pub ean: String,
+    pub quantity: i32,
+}
+
+/// An EAN that appeared on more than one line; its quantities were added up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedDuplicate {
+    pub ean: String,
+    /// How many lines listed it.
+    pub occurrences: usize,
+}
+
+/// A line that is neither empty nor an `EAN[,quantity]` pair, e.g. a header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedLine {
+    /// 1-based line number in the pasted text or file.
+    pub line_number: usize,
+    pub text: String,
+}
+
+/// A pasted or loaded EAN list after parsing, one entry per distinct EAN in
+/// the order they first appeared.
+#[derive(Debug, Clone, Default)]
+pub struct EanList {
+    pub entries: Vec<ImportEntry>,
+    pub duplicates: Vec<MergedDuplicate>,
+    pub skipped: Vec<SkippedLine>,
+}
+
+/// Parses lines of `EAN[,quantity]`.
+///
+/// The quantity may also be separated by `;`, a tab or spaces, as spreadsheet
+/// and chat copies tend to be, and defaults to 1. Empty lines are ignored;
+/// anything else that does not parse is reported in [`EanList::skipped`].
+pub fn parse_ean_list(text: &str) -> EanList {
+    let mut list = EanList::default();
+    let mut occurrences: Vec<usize> = Vec::new();
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = raw_line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() {
+            continue;
+        }
+        let Some(entry) = parse_line(line) else {
+            list.skipped.push(SkippedLine {
+                line_number: index + 1,
+                text: line.to_string(),
+            });
+            continue;
+        };
+        match list.entries.iter().position(|e| e.ean == entry.ean) {
+            Some(position) => {
+                list.entries[position].quantity += entry.quantity;
+                occurrences[position] += 1;
+            }
+            None => {
+                list.entries.push(entry);
+                occurrences.push(1);
+            }
+        }
+    }
+    list.duplicates = list
+        .entries
+        .iter()
+        .zip(occurrences)
+        .filter(|(_, count)| *count > 1)
+        .map(|(entry, count)| MergedDuplicate {
+            ean: entry.ean.clone(),
+            occurrences: count,
+        })
+        .collect();
+    list
+}
+
+fn parse_line(line: &str) -> Option<ImportEntry> {
+    let mut fields = line
+        .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .map(|field| field.trim_matches('"'))
+        .filter(|field| !field.is_empty());
+    let ean = fields.next()?;
+    if ean.len() < 8 || !ean.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let quantity = match fields.next() {
+        Some(quantity) => quantity.parse().ok().filter(|q: &i32| *q > 0)?,
+        None => 1,
+    };
+    if fields.next().is_some() {
+        return None;
+    }
+    Some(ImportEntry {
+        ean: ean.to_string(),
+        quantity,
+    })
+}
+
+/// What happened to every line of an imported EAN list.
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Products found, with `quantity` set to the amount wanted.
+    pub found: Vec<Product>,
+    /// EANs the storefront does not know.
+    pub not_found: Vec<String>,
+    /// EANs that could not be looked up, e.g. because the network is down.
+    pub failed: Vec<(String, Error)>,
+    pub duplicates: Vec<MergedDuplicate>,
+    pub skipped: Vec<SkippedLine>,
+}
+
+impl ImportReport {
+    /// Starts a report for `list`, carrying over its merged and skipped lines.
+    pub fn new(list: &EanList) -> Self {
+        Self {
+            duplicates: list.duplicates.clone(),
+            skipped: list.skipped.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Records the lookup result for `entry`.
+    pub fn record(&mut self, entry: &ImportEntry, result: Result<Product>) {
+        match result {
+            Ok(mut product) => {
+                product.quantity = entry.quantity;
+                self.found.push(product);
+            }
+            Err(Error::NotFound(_)) => self.not_found.push(entry.ean.clone()),
+            Err(e) => self.failed.push((entry.ean.clone(), e)),
+        }
+    }
+
+    /// Adds every found product to `cart` at the cart's rate of its currency
+    /// (see [`Cart::rates`]) and returns how many lines were added or
+    /// increased.
+    pub fn add_to_cart(&self, cart: &mut Cart, rates: &ExchangeRates) -> usize {
+        self.found
+            .iter()
+            .filter(|product| {
+                let rate = cart.rates(rates).get(product.currency());
+                cart.add((*product).clone(), rate)
+            })
+            .count()
+    }
+}
+
+/// Looks up every entry of `list` in the `country` storefront through
+/// [`lookup::fetch_product_info`], so cached products are not fetched again.
+pub fn resolve(
+    source: &dyn ProductSource,
+    country: Country,
+    list: &EanList,
+    cache: &mut ProductCache,
+) -> ImportReport {
+    let mut report = ImportReport::new(list);
+    for entry in &list.entries {
+        let result = lookup::fetch_product_info(source, country, &entry.ean, cache);
+        report.record(entry, result);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(ean: &str, quantity: i32) -> ImportEntry {
+        ImportEntry {
+            ean: ean.to_string(),
+            quantity,
+        }
+    }
+
+    #[test]
+    fn parses_quantities_with_any_separator() {
+        let list = parse_ean_list(
+            "\u{feff}4058172936760,2\n4066447000001;3\n4000000000017\t4\n40000000 5\n\n4011111111111",
+        );
+        assert_eq!(
+            list.entries,
+            vec![
+                entry("4058172936760", 2),
+                entry("4066447000001", 3),
+                entry("4000000000017", 4),
+                entry("40000000", 5),
+                entry("4011111111111", 1),
+            ]
+        );
+        assert!(list.duplicates.is_empty());
+        assert!(list.skipped.is_empty());
+    }
+
+    #[test]
+    fn merges_duplicates_in_first_seen_order() {
+        let list = parse_ean_list("4058172936760,2\n4066447000001\n\"4058172936760\";3\n");
+        assert_eq!(
+            list.entries,
+            vec![entry("4058172936760", 5), entry("4066447000001", 1)]
+        );
+        assert_eq!(
+            list.duplicates,
+            vec![MergedDuplicate {
+                ean: "4058172936760".to_string(),
+                occurrences: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn skips_lines_that_are_not_ean_pairs() {
+        let list = parse_ean_list("EAN;Ilość\n4058172936760,0\n4058172936760,-1\n1234567\n4058172936760,2,x\n4058172936760,1");
+        assert_eq!(list.entries, vec![entry("4058172936760", 1)]);
+        let skipped: Vec<usize> = list.skipped.iter().map(|s| s.line_number).collect();
+        assert_eq!(skipped, vec![1, 2, 3, 4, 5]);
+        assert_eq!(list.skipped[0].text, "EAN;Ilość");
+    }
+}
@@ -0,0 +1,64 @@
+//! Core of DMHelper: the product model, dm.de lookup and keyword search,
+//! product cache, cart, price conversion, price history, watchlist and NBP
+//! exchange rates, independent of the egui front-end.
+//!
+//! ```no_run
+//! use dmhelper_core::{
+//!     lookup, Cart, Country, Currency, DmSource, ExchangeRates, PlnRounding, ProductCache, Rate,
+//! };
+//!
+//! let source = DmSource::new();
+//! let mut cache = ProductCache::new();
+//! let mut rates = ExchangeRates::new();
+//! rates.set_override(Currency::Eur, Rate::from_f64(4.3));
+//!
+//! let mut cart = Cart::new();
+//! let mut product =
+//!     lookup::fetch_product_info(&source, Country::De, "4058172936760", &mut cache).unwrap();
+//! product.quantity = 2;
+//! cart.add(product, rates.get(Currency::Eur));
+//! println!("{} PLN", cart.total_pln(PlnRounding::default()));
+//! ```
+
+pub mod budget;
+pub mod cache;
+pub mod cart;
+pub mod carts;
+pub mod country;
+pub mod error;
+pub mod export;
+pub mod history;
+pub mod import;
+pub mod lookup;
+pub mod money;
+pub mod prices;
+pub mod pricing;
+pub mod product;
+pub mod rates;
+pub mod resale;
+pub mod search;
+pub mod settings;
+pub mod source;
+pub mod storage;
+pub mod watchlist;
+pub mod worker;
+
+pub use budget::{Budget, BudgetStatus};
+pub use cache::{CachedItem, ProductCache};
+pub use cart::{BuyerTotal, Cart, CartLine, Share};
+pub use carts::CartList;
+pub use country::{Country, Currency};
+pub use error::{Error, Result};
+pub use history::{CartHistory, CartOp};
+pub use import::{EanList, ImportEntry, ImportReport};
+pub use lookup::fetch_product_info;
+pub use money::{Money, Rate, RoundingMode};
+pub use prices::{PriceHistory, PriceObservation, PriceSeries};
+pub use pricing::{ExchangeRates, PlnRounding, PublishedRate};
+pub use product::{Product, UnitPrice};
+pub use resale::{ResaleQuote, ResaleRules, TripCostSplit};
+pub use search::SearchHit;
+pub use settings::Settings;
+pub use source::{DmSource, FixtureSource, ProductSource};
+pub use watchlist::{WatchItem, Watchlist};
+pub use worker::{CacheSaveTask, ImageTask, LookupTask, RatesTask, SearchTask, SearchUpdate};
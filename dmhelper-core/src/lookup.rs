@@ -0,0 +1,52 @@
+use image::{io::Reader, DynamicImage};
+use reqwest::Url;
+use std::io::Cursor;
+
+use crate::{
+    cache::ProductCache,
+    country::Country,
+    error::{self, Error, Result},
+    product::Product,
+    source::ProductSource,
+};
+
+/// Looks up a product by EAN in the `country` storefront, serving it from
+/// `cache` when possible.
+///
+/// On a cache miss the product and its image are fetched from `source` and
+/// stored in the cache.
+pub fn fetch_product_info(
+    source: &dyn ProductSource,
+    country: Country,
+    ean: &str,
+    cache: &mut ProductCache,
+) -> Result<Product> {
+    if let Some(product) = cache.get(country, ean) {
+        return Ok(product);
+    }
+    let mut product = source.fetch_product(country, ean)?;
+    product.image = source.fetch_image(&product, 0).ok();
+    cache.insert(country, ean, product.clone());
+    Ok(product)
+}
+
+/// Downloads an image and returns its encoded bytes.
+pub fn download_image(input_url: &str) -> Result<Vec<u8>> {
+    let url = match Url::parse(input_url) {
+        Ok(url) => url,
+        Err(e) => {
+            return Err(Error::Image(format!("{}: {}", input_url, e)));
+        }
+    };
+    let response = error::check_status(reqwest::blocking::get(url)?)?;
+
+    Ok(response.bytes()?.to_vec())
+}
+
+/// Decodes image bytes as returned by [`download_image`].
+pub fn decode_image(bytes: &[u8]) -> Result<DynamicImage> {
+    let cursor = Cursor::new(bytes);
+    let image = Reader::new(cursor).with_guessed_format()?.decode()?;
+
+    Ok(image)
+}
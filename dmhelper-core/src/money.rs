@@ -0,0 +1,434 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Neg, Sub, SubAssign},
+};
+
+/// Number of rate units in one PLN per currency unit; NBP publishes at most
+/// six decimal places.
+const RATE_SCALE: i128 = 1_000_000;
+
+/// How a converted amount is rounded to whole grosze.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RoundingMode {
+    /// Halves are rounded away from zero (the usual shop rounding).
+    #[default]
+    HalfUp,
+    /// Halves are rounded to the even neighbour (banker's rounding).
+    HalfEven,
+    /// Always towards zero.
+    Down,
+    /// Always away from zero.
+    Up,
+}
+
+impl RoundingMode {
+    pub const ALL: [RoundingMode; 4] = [
+        RoundingMode::HalfUp,
+        RoundingMode::HalfEven,
+        RoundingMode::Down,
+        RoundingMode::Up,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RoundingMode::HalfUp => "połówki w górę",
+            RoundingMode::HalfEven => "połówki do parzystej",
+            RoundingMode::Down => "w dół",
+            RoundingMode::Up => "w górę",
+        }
+    }
+
+    /// Divides `value` by `divisor` (positive), rounding the quotient.
+    fn divide(self, value: i128, divisor: i128) -> i128 {
+        let quotient = value / divisor;
+        let remainder = value % divisor;
+        if remainder == 0 {
+            return quotient;
+        }
+        let away = if value < 0 {
+            quotient - 1
+        } else {
+            quotient + 1
+        };
+        let twice = remainder.abs() * 2;
+        match self {
+            RoundingMode::Down => quotient,
+            RoundingMode::Up => away,
+            RoundingMode::HalfUp => {
+                if twice >= divisor {
+                    away
+                } else {
+                    quotient
+                }
+            }
+            RoundingMode::HalfEven => {
+                if twice > divisor || (twice == divisor && quotient % 2 != 0) {
+                    away
+                } else {
+                    quotient
+                }
+            }
+        }
+    }
+}
+
+/// An exact amount of money in hundredths of a currency unit (cents,
+/// haléře, bani, grosze...).
+///
+/// The currency is not part of the value; it comes from the context the
+/// amount is used in. Serialized as a decimal string such as `"3.95"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub fn from_minor(minor: i64) -> Self {
+        Money(minor)
+    }
+
+    /// The amount in hundredths.
+    pub fn minor(self) -> i64 {
+        self.0
+    }
+
+    /// Parses a decimal amount such as `"3.95"`, `"3,95"`, `"-12"` or
+    /// `"1 299,00"`. More than two decimal places are rejected rather than
+    /// silently rounded.
+    pub fn parse(input: &str) -> Option<Self> {
+        let cleaned: String = input
+            .trim()
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '\u{a0}')
+            .map(|c| if c == ',' { '.' } else { c })
+            .collect();
+        let (negative, digits) = match cleaned.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, cleaned.as_str()),
+        };
+        let (whole, fraction) = match digits.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (digits, ""),
+        };
+        if (whole.is_empty() && fraction.is_empty())
+            || fraction.len() > 2
+            || !whole.chars().all(|c| c.is_ascii_digit())
+            || !fraction.chars().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        let whole: i64 = if whole.is_empty() {
+            0
+        } else {
+            whole.parse().ok()?
+        };
+        let fraction: i64 = match fraction.len() {
+            0 => 0,
+            1 => fraction.parse::<i64>().ok()? * 10,
+            _ => fraction.parse().ok()?,
+        };
+        let minor = whole.checked_mul(100)?.checked_add(fraction)?;
+        Some(Money(if negative { -minor } else { minor }))
+    }
+
+    /// Converts a floating point amount, rounding to the nearest hundredth.
+    pub fn from_f64(amount: f64) -> Self {
+        Money((amount * 100.0).round() as i64)
+    }
+
+    /// Approximate value for widgets that only edit floats.
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+
+    /// The amount for `quantity` units at this unit price.
+    pub fn times(self, quantity: i32) -> Self {
+        Money(self.0 * quantity as i64)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// `basis_points` hundredths of a percent of this amount, rounded to
+    /// hundredths with `mode`.
+    pub fn percent(self, basis_points: i64, mode: RoundingMode) -> Self {
+        Money(mode.divide(self.0 as i128 * basis_points as i128, 10_000) as i64)
+    }
+
+    /// The price of one of `quantity` units this amount pays for, rounded to
+    /// hundredths with `mode`.
+    pub fn per_unit(self, quantity: i32, mode: RoundingMode) -> Self {
+        if quantity <= 0 {
+            return self;
+        }
+        Money(mode.divide(self.0 as i128, quantity as i128) as i64)
+    }
+
+    /// Splits the amount in proportion to `weights` so that the parts add
+    /// up exactly; leftover hundredths go to the largest remainders. With no
+    /// positive weight everything is split evenly.
+    pub fn allocate(self, weights: &[i64]) -> Vec<Money> {
+        if weights.is_empty() {
+            return Vec::new();
+        }
+        let weights: Vec<i128> = if weights.iter().any(|w| *w > 0) {
+            weights.iter().map(|w| (*w).max(0) as i128).collect()
+        } else {
+            vec![1; weights.len()]
+        };
+        let total_weight: i128 = weights.iter().sum();
+        let amount = self.0 as i128;
+        let mut parts: Vec<i128> = weights.iter().map(|w| amount * w / total_weight).collect();
+        let mut remainders: Vec<(usize, i128)> = weights
+            .iter()
+            .enumerate()
+            .map(|(index, w)| (index, (amount * w % total_weight).abs()))
+            .collect();
+        remainders.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        let leftover = amount - parts.iter().sum::<i128>();
+        let step = leftover.signum();
+        for (index, _) in remainders.iter().take(leftover.unsigned_abs() as usize) {
+            parts[*index] += step;
+        }
+        parts.into_iter().map(|part| Money(part as i64)).collect()
+    }
+}
+
+impl fmt::Display for Money {
+    /// Formats with exactly two decimal places, e.g. `3.95`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        let text = format!("{}{}.{:02}", sign, abs / 100, abs % 100);
+        f.pad(&text)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+    fn sub(self, other: Money) -> Money {
+        Money(self.0 - other.0)
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        self.0 -= other.0;
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    /// Accepts decimal strings as well as plain numbers, which older cart and
+    /// cache files stored prices as.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value: Value = Deserialize::deserialize(deserializer)?;
+        match value {
+            Value::String(text) => Money::parse(&text)
+                .ok_or_else(|| serde::de::Error::custom(format!("Niepoprawna kwota: {}", text))),
+            Value::Number(num) => num
+                .as_f64()
+                .map(Money::from_f64)
+                .ok_or_else(|| serde::de::Error::custom("Niepoprawna kwota")),
+            _ => Err(serde::de::Error::custom("Unexpected amount type")),
+        }
+    }
+}
+
+/// An exchange rate in PLN per currency unit with six decimal places.
+///
+/// Serialized as a decimal string such as `"4.3012"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Rate(i64);
+
+impl Rate {
+    pub const ZERO: Rate = Rate(0);
+
+    /// Converts a floating point rate, rounding to six decimal places.
+    pub fn from_f64(rate: f64) -> Self {
+        Rate((rate * RATE_SCALE as f64).round() as i64)
+    }
+
+    /// Approximate value for widgets that only edit floats.
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / RATE_SCALE as f64
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// `amount` in PLN, in hundredths of a grosz scaled by the rate precision;
+    /// used to sum several conversions before rounding once.
+    pub fn convert_exact(self, amount: Money) -> i128 {
+        amount.0 as i128 * self.0 as i128
+    }
+
+    /// `amount` converted to PLN and rounded to grosze with `mode`.
+    pub fn convert(self, amount: Money, mode: RoundingMode) -> Money {
+        round_exact(self.convert_exact(amount), mode)
+    }
+
+    /// A PLN amount converted back to the rate's currency and rounded to
+    /// hundredths with `mode`; zero if the rate is unknown.
+    pub fn convert_back(self, pln: Money, mode: RoundingMode) -> Money {
+        if self.0 <= 0 {
+            return Money::ZERO;
+        }
+        Money(mode.divide(pln.0 as i128 * RATE_SCALE, self.0 as i128) as i64)
+    }
+}
+
+/// Rounds a sum of [`Rate::convert_exact`] results to grosze.
+pub fn round_exact(exact: i128, mode: RoundingMode) -> Money {
+    Money(mode.divide(exact, RATE_SCALE) as i64)
+}
+
+impl fmt::Display for Rate {
+    /// Formats with up to six decimal places and at least four, e.g. `4.3012`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        let mut fraction = format!("{:06}", abs % RATE_SCALE as u64);
+        while fraction.len() > 4 && fraction.ends_with('0') {
+            fraction.pop();
+        }
+        let text = format!("{}{}.{}", sign, abs / RATE_SCALE as u64, fraction);
+        f.pad(&text)
+    }
+}
+
+impl Serialize for Rate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rate {
+    /// Accepts decimal strings as well as plain numbers, which older files
+    /// stored rates as.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value: Value = Deserialize::deserialize(deserializer)?;
+        let rate = match value {
+            Value::String(text) => text.trim().replace(',', ".").parse::<f64>().ok(),
+            Value::Number(num) => num.as_f64(),
+            _ => None,
+        };
+        rate.map(Rate::from_f64)
+            .ok_or_else(|| serde::de::Error::custom("Niepoprawny kurs"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn divide_rounds_negative_halves() {
+        assert_eq!(RoundingMode::HalfUp.divide(-250, 100), -3);
+        assert_eq!(RoundingMode::HalfEven.divide(-250, 100), -2);
+        assert_eq!(RoundingMode::HalfEven.divide(-350, 100), -4);
+        assert_eq!(RoundingMode::Down.divide(-250, 100), -2);
+        assert_eq!(RoundingMode::Up.divide(-249, 100), -3);
+        assert_eq!(RoundingMode::HalfUp.divide(-249, 100), -2);
+    }
+
+    #[test]
+    fn divide_rounds_positive_halves() {
+        assert_eq!(RoundingMode::HalfUp.divide(250, 100), 3);
+        assert_eq!(RoundingMode::HalfEven.divide(250, 100), 2);
+        assert_eq!(RoundingMode::HalfEven.divide(350, 100), 4);
+        assert_eq!(RoundingMode::Down.divide(299, 100), 2);
+        assert_eq!(RoundingMode::Up.divide(201, 100), 3);
+        assert_eq!(RoundingMode::HalfUp.divide(300, 100), 3);
+    }
+
+    #[test]
+    fn parse_accepts_polish_and_plain_amounts() {
+        assert_eq!(Money::parse("3.95"), Some(Money(395)));
+        assert_eq!(Money::parse("3,95"), Some(Money(395)));
+        assert_eq!(Money::parse("-12"), Some(Money(-1200)));
+        assert_eq!(Money::parse("1 299,00"), Some(Money(129_900)));
+        assert_eq!(Money::parse("1\u{a0}299,5"), Some(Money(129_950)));
+        assert_eq!(Money::parse(",5"), Some(Money(50)));
+    }
+
+    #[test]
+    fn parse_rejects_ambiguous_amounts() {
+        assert_eq!(Money::parse("1.299,00"), None);
+        assert_eq!(Money::parse("1,299.00"), None);
+        assert_eq!(Money::parse("3.955"), None);
+        assert_eq!(Money::parse(""), None);
+        assert_eq!(Money::parse("-"), None);
+        assert_eq!(Money::parse("12 zł"), None);
+    }
+
+    #[test]
+    fn allocate_sums_exactly() {
+        let parts = Money(100).allocate(&[1, 1, 1]);
+        assert_eq!(parts, vec![Money(34), Money(33), Money(33)]);
+
+        let parts = Money(-100).allocate(&[1, 1, 1]);
+        assert_eq!(parts.iter().copied().sum::<Money>(), Money(-100));
+
+        let amount = Money(123_457);
+        let parts = amount.allocate(&[3, 7, 0, 11]);
+        assert_eq!(parts.iter().copied().sum::<Money>(), amount);
+        assert_eq!(parts[2], Money::ZERO);
+    }
+
+    #[test]
+    fn allocate_without_weights_splits_evenly() {
+        assert_eq!(
+            Money(10).allocate(&[0, 0, 0]),
+            vec![Money(4), Money(3), Money(3)]
+        );
+        assert!(Money(10).allocate(&[]).is_empty());
+    }
+
+    #[test]
+    fn convert_rounds_once() {
+        let rate = Rate::from_f64(4.3012);
+        assert_eq!(rate.convert(Money(195), RoundingMode::HalfUp), Money(839));
+        assert_eq!(rate.convert(Money(195), RoundingMode::Down), Money(838));
+        assert_eq!(
+            Rate::ZERO.convert_back(Money(100), RoundingMode::HalfUp),
+            Money::ZERO
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    country::{Country, Currency},
+    error::Result,
+    money::{Money, Rate, RoundingMode},
+    pricing,
+    product::Product,
+    storage,
+};
+
+/// An unchanged price seen again within this many minutes is not recorded.
+pub const MIN_OBSERVATION_INTERVAL_MINUTES: i64 = 60;
+
+/// A price a product was seen at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceObservation {
+    pub at: DateTime<Utc>,
+    /// Unit price in the storefront's currency.
+    pub price: Money,
+    /// PLN rate of the storefront's currency at the time.
+    pub rate: Rate,
+}
+
+impl PriceObservation {
+    pub fn pln(&self, mode: RoundingMode) -> Money {
+        pricing::to_pln(self.price, self.rate, mode)
+    }
+}
+
+/// Every price recorded for one product in one storefront, oldest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceSeries {
+    pub country: Country,
+    pub ean: String,
+    /// Product name when it was last seen.
+    pub name: String,
+    pub observations: Vec<PriceObservation>,
+}
+
+impl PriceSeries {
+    pub fn currency(&self) -> Currency {
+        self.country.currency()
+    }
+
+    /// The earliest observation of the lowest price.
+    pub fn min(&self) -> Option<&PriceObservation> {
+        self.observations
+            .iter()
+            .min_by_key(|observation| observation.price)
+    }
+
+    /// The earliest observation of the highest price.
+    pub fn max(&self) -> Option<&PriceObservation> {
+        self.observations
+            .iter()
+            .rev()
+            .max_by_key(|observation| observation.price)
+    }
+
+    pub fn last(&self) -> Option<&PriceObservation> {
+        self.observations.last()
+    }
+
+    /// The most recent price change as the observations before and after it.
+    pub fn last_change(&self) -> Option<(&PriceObservation, &PriceObservation)> {
+        self.observations
+            .windows(2)
+            .rev()
+            .find(|pair| pair[0].price != pair[1].price)
+            .map(|pair| (&pair[0], &pair[1]))
+    }
+}
+
+/// Prices products were looked up at, kept per EAN and storefront.
+#[derive(Debug, Clone, Default)]
+pub struct PriceHistory {
+    series: Vec<PriceSeries>,
+}
+
+impl PriceHistory {
+    /// Loads the history from `path`; a missing file is an empty history.
+    pub fn load(path: &Path) -> Result<Self> {
+        let series = storage::load_json(path)?.unwrap_or_default();
+        Ok(Self { series })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        storage::save_json(path, &self.series)
+    }
+
+    /// Every recorded product, in the order they were first seen.
+    pub fn series(&self) -> &[PriceSeries] {
+        &self.series
+    }
+
+    pub fn get(&self, country: Country, ean: &str) -> Option<&PriceSeries> {
+        self.series
+            .iter()
+            .find(|series| series.country == country && series.ean == ean)
+    }
+
+    /// Records the price of `product` seen at `at`, with `rate` as the PLN
+    /// rate of its currency; returns whether an observation was added.
+    ///
+    /// Products without a price and an unchanged price seen again within
+    /// [`MIN_OBSERVATION_INTERVAL_MINUTES`] are skipped.
+    pub fn record(&mut self, product: &Product, rate: Rate, at: DateTime<Utc>) -> bool {
+        if product.price <= Money::ZERO {
+            return false;
+        }
+        let index = match self
+            .series
+            .iter()
+            .position(|series| series.country == product.country && series.ean == product.ean)
+        {
+            Some(index) => index,
+            None => {
+                self.series.push(PriceSeries {
+                    country: product.country,
+                    ean: product.ean.clone(),
+                    name: product.name.clone(),
+                    observations: Vec::new(),
+                });
+                self.series.len() - 1
+            }
+        };
+        let series = &mut self.series[index];
+        series.name.clone_from(&product.name);
+        if let Some(last) = series.observations.last() {
+            if last.price == product.price
+                && at - last.at < Duration::minutes(MIN_OBSERVATION_INTERVAL_MINUTES)
+            {
+                return false;
+            }
+        }
+        series.observations.push(PriceObservation {
+            at,
+            price: product.price,
+            rate,
+        });
+        true
+    }
+}
+
+pub fn price_history_path() -> PathBuf {
+    storage::data_dir().join("price_history.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::test_product;
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, hour, 0, 0).unwrap()
+    }
+
+    fn series(prices: &[i64]) -> PriceSeries {
+        PriceSeries {
+            country: Country::De,
+            ean: "4058172936760".to_string(),
+            name: "Produkt".to_string(),
+            observations: prices
+                .iter()
+                .enumerate()
+                .map(|(hour, &price)| PriceObservation {
+                    at: at(hour as u32),
+                    price: Money::from_minor(price),
+                    rate: Rate::from_f64(4.3),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn min_and_max_are_the_earliest_extreme_observations() {
+        let prices = series(&[195, 145, 245, 145, 245]);
+        assert_eq!(prices.min().unwrap().at, at(1));
+        assert_eq!(prices.max().unwrap().at, at(2));
+
+        let empty = series(&[]);
+        assert!(empty.min().is_none());
+        assert!(empty.max().is_none());
+    }
+
+    #[test]
+    fn last_change_skips_unchanged_prices() {
+        let changed = series(&[195, 145, 145, 245, 245]);
+        let (before, after) = changed.last_change().unwrap();
+        assert_eq!((before.at, after.at), (at(2), at(3)));
+        assert!(series(&[195, 195]).last_change().is_none());
+        assert!(series(&[195]).last_change().is_none());
+    }
+
+    #[test]
+    fn record_skips_an_unchanged_price_seen_again_soon() {
+        let mut history = PriceHistory::default();
+        let mut product = test_product("4058172936760", 1);
+        let rate = Rate::from_f64(4.3);
+        let soon = at(0) + Duration::minutes(MIN_OBSERVATION_INTERVAL_MINUTES - 1);
+
+        assert!(history.record(&product, rate, at(0)));
+        assert!(!history.record(&product, rate, soon));
+        assert!(history.record(&product, rate, at(1)));
+
+        product.price = Money::from_minor(145);
+        assert!(history.record(&product, rate, at(1)));
+
+        product.price = Money::ZERO;
+        assert!(!history.record(&product, rate, at(5)));
+
+        product.country = Country::At;
+        product.price = Money::from_minor(145);
+        assert!(history.record(&product, rate, at(1)));
+
+        let de = history.get(Country::De, &product.ean).unwrap();
+        assert_eq!(de.observations.len(), 3);
+        assert_eq!(history.series().len(), 2);
+    }
+}
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    country::Currency,
+    money::{self, Money, Rate, RoundingMode},
+};
+
+/// A mid rate published by NBP.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublishedRate {
+    /// PLN for one unit of the currency.
+    pub mid: Rate,
+    /// Date of the NBP table the rate comes from, `YYYY-MM-DD`.
+    pub effective_date: String,
+}
+
+/// PLN exchange rates per storefront currency.
+///
+/// Rates typed in by hand override the ones published by NBP until they are
+/// cleared again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExchangeRates {
+    published: HashMap<Currency, PublishedRate>,
+    overrides: HashMap<Currency, Rate>,
+}
+
+impl ExchangeRates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// PLN for one unit of `currency`, zero if no rate is known.
+    pub fn get(&self, currency: Currency) -> Rate {
+        match self.overrides.get(&currency) {
+            Some(rate) => *rate,
+            None => self
+                .published
+                .get(&currency)
+                .map(|rate| rate.mid)
+                .unwrap_or(Rate::ZERO),
+        }
+    }
+
+    /// The last rate published by NBP, even if it is currently overridden.
+    pub fn published(&self, currency: Currency) -> Option<&PublishedRate> {
+        self.published.get(&currency)
+    }
+
+    pub fn set_published(&mut self, currency: Currency, rate: PublishedRate) {
+        self.published.insert(currency, rate);
+    }
+
+    pub fn is_overridden(&self, currency: Currency) -> bool {
+        self.overrides.contains_key(&currency)
+    }
+
+    /// Uses `rate` for `currency` regardless of the published rate.
+    pub fn set_override(&mut self, currency: Currency, rate: Rate) {
+        self.overrides.insert(currency, rate);
+    }
+
+    /// Goes back to the published rate for `currency`.
+    pub fn clear_override(&mut self, currency: Currency) {
+        self.overrides.remove(&currency);
+    }
+}
+
+/// How PLN amounts are rounded to grosze.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct PlnRounding {
+    pub mode: RoundingMode,
+    /// Round every cart line on its own and add up the rounded amounts;
+    /// otherwise the exact line amounts are added up and only the total is
+    /// rounded.
+    pub per_line: bool,
+}
+
+/// Converts an amount to PLN using `exchange_rate` (PLN per unit).
+pub fn to_pln(amount: Money, exchange_rate: Rate, mode: RoundingMode) -> Money {
+    exchange_rate.convert(amount, mode)
+}
+
+/// Converts several `(amount, exchange_rate)` pairs to PLN and adds them up,
+/// rounding as configured in `rounding`.
+pub fn sum_pln<I>(amounts: I, rounding: PlnRounding) -> Money
+where
+    I: IntoIterator<Item = (Money, Rate)>,
+{
+    let amounts = amounts.into_iter();
+    if rounding.per_line {
+        amounts
+            .map(|(amount, rate)| rate.convert(amount, rounding.mode))
+            .sum()
+    } else {
+        let exact: i128 = amounts
+            .map(|(amount, rate)| rate.convert_exact(amount))
+            .sum();
+        money::round_exact(exact, rounding.mode)
+    }
+}
+
+/// Price of `quantity` units at `unit_price`.
+pub fn line_total(unit_price: Money, quantity: i32) -> Money {
+    unit_price.times(quantity)
+}
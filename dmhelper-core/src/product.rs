@@ -0,0 +1,414 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use crate::{
+    country::{Country, Currency},
+    error::Error,
+    money::Money,
+};
+
+/// Raw product payload returned by the dm.de product detail endpoint.
+#[derive(Deserialize, Debug)]
+pub struct ApiResponse {
+    #[serde(deserialize_with = "deserialize_ean")]
+    pub gtin: String,
+    pub title: Title,
+    pub price: Price,
+    #[serde(deserialize_with = "deserialize_image")]
+    pub images: Vec<Image>,
+    #[serde(default)]
+    pub brand: Option<Brand>,
+    #[serde(default, rename = "descriptionGroups")]
+    pub description_groups: Vec<DescriptionGroup>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Title {
+    pub headline: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Brand {
+    pub name: String,
+}
+
+/// A titled section of the product description, e.g. "Produktbeschreibung".
+#[derive(Deserialize, Debug)]
+pub struct DescriptionGroup {
+    #[serde(default)]
+    pub header: String,
+    /// Blocks of `texts` or `bulletpoints`; other kinds are ignored.
+    #[serde(default, rename = "contentBlock")]
+    pub content_block: Vec<Value>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Image {
+    pub src: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Price {
+    pub price: String,
+    /// Net content and base price, e.g. `"0,3 l (6,50 € je 1 l)"`.
+    #[serde(default)]
+    pub infos: Vec<String>,
+}
+
+/// Price per base unit as printed next to the product, e.g. 6,50 per `1 l`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnitPrice {
+    /// In the storefront's currency.
+    pub price: Money,
+    /// The base unit, e.g. `1 l` or `100 g`.
+    pub unit: String,
+}
+
+/// A product as shown in the product panel and stored in the cart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Product {
+    pub ean: String,
+    pub name: String,
+    /// Storefront the product was looked up in.
+    pub country: Country,
+    /// Unit price in the storefront's currency, see [`Product::currency`].
+    pub price: Money,
+    pub quantity: i32,
+    #[serde(default)]
+    pub brand: Option<String>,
+    /// Net content, e.g. `300 ml`.
+    #[serde(default)]
+    pub size: Option<String>,
+    #[serde(default)]
+    pub unit_price: Option<UnitPrice>,
+    /// Product description as plain text, sections separated by blank lines.
+    #[serde(default)]
+    pub description: String,
+    /// URLs of every product image, the main one first; empty for products
+    /// without pictures.
+    #[serde(
+        default,
+        alias = "image_url",
+        deserialize_with = "deserialize_image_urls"
+    )]
+    pub image_urls: Vec<String>,
+    /// Encoded bytes (jpeg/png) of the main image, the first of `image_urls`.
+    #[serde(default, with = "base64_image")]
+    pub image: Option<Vec<u8>>,
+}
+
+/// Stores image bytes as a base64 string instead of a JSON number array.
+mod base64_image {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(image: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match image {
+            Some(bytes) => serializer.serialize_some(&STANDARD.encode(bytes)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let encoded: Option<String> = Option::deserialize(deserializer)?;
+        encoded
+            .map(|encoded| STANDARD.decode(encoded).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+impl Product {
+    pub fn currency(&self) -> Currency {
+        self.country.currency()
+    }
+
+    /// URL of the main image, if the product has any.
+    pub fn image_url(&self) -> Option<&str> {
+        self.image_urls.first().map(String::as_str)
+    }
+
+    /// The base price with its currency, e.g. `6.50 EUR / 1 l`.
+    pub fn unit_price_label(&self) -> Option<String> {
+        self.unit_price.as_ref().map(|unit_price| {
+            format!(
+                "{} {} / {}",
+                unit_price.price,
+                self.currency(),
+                unit_price.unit
+            )
+        })
+    }
+}
+
+/// A 1.95 EUR product from dm.de with just a name, for tests.
+#[cfg(test)]
+pub(crate) fn test_product(ean: &str, quantity: i32) -> Product {
+    Product {
+        ean: ean.to_string(),
+        name: format!("Produkt {}", ean),
+        country: Country::De,
+        price: Money::from_minor(195),
+        quantity,
+        brand: None,
+        size: None,
+        unit_price: None,
+        description: String::new(),
+        image_urls: Vec::new(),
+        image: None,
+    }
+}
+
+/// What separates the base price from its unit in the storefront
+/// languages, e.g. `je` in `6,50 € je 1 l` or `za` in `216,33 Kč za 1 l`.
+const UNIT_PRICE_SEPARATORS: [&str; 6] = [" je ", " za ", " pe ", " per ", " за ", "/"];
+
+/// Splits a price info such as `"0,3 l (6,50 € je 1 l)"` into the net
+/// content and the base price; either may be missing.
+///
+/// A base price in a format not understood here is kept with the net
+/// content as it was printed, so nothing is lost.
+fn parse_price_info(info: &str) -> (Option<String>, Option<UnitPrice>) {
+    let info = info.trim();
+    let (size, base) = match info.split_once('(') {
+        Some((size, base)) => (size.trim(), Some(base.trim().trim_end_matches(')'))),
+        None => (info, None),
+    };
+    let unit_price = base.and_then(parse_unit_price);
+    let size = if base.is_some() && unit_price.is_none() {
+        info
+    } else {
+        size
+    };
+    let size = (!size.is_empty()).then(|| size.to_string());
+    (size, unit_price)
+}
+
+/// Reads a base price written either as `<price> <separator> <unit>` (see
+/// [`UNIT_PRICE_SEPARATORS`]) or as `<unit> = <price>`.
+fn parse_unit_price(base: &str) -> Option<UnitPrice> {
+    let (price, unit) = match base.split_once('=') {
+        Some((unit, price)) => (price, unit),
+        None => UNIT_PRICE_SEPARATORS
+            .iter()
+            .find_map(|separator| base.split_once(separator))?,
+    };
+    let unit = unit.trim();
+    if unit.is_empty() {
+        return None;
+    }
+    Some(UnitPrice {
+        price: parse_amount(price)?,
+        unit: unit.to_string(),
+    })
+}
+
+/// The first number in `text`, ignoring the currency around it and spaces
+/// between thousands, e.g. 2163.33 in `"2 163,33 Ft"`.
+pub(crate) fn parse_amount(text: &str) -> Option<Money> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let number: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || matches!(c, ',' | '.' | ' ' | '\u{a0}'))
+        .collect();
+    Money::parse(number.trim_end_matches([',', '.', ' ', '\u{a0}']))
+}
+
+/// Flattens the description groups into plain text: each group's header
+/// followed by its texts and bullet points.
+fn description_text(groups: &[DescriptionGroup]) -> String {
+    let mut sections = Vec::new();
+    for group in groups {
+        let mut lines = Vec::new();
+        if !group.header.is_empty() {
+            lines.push(group.header.clone());
+        }
+        for block in &group.content_block {
+            for (key, prefix) in [("texts", ""), ("bulletpoints", "• ")] {
+                if let Some(Value::Array(items)) = block.get(key) {
+                    lines.extend(
+                        items
+                            .iter()
+                            .filter_map(Value::as_str)
+                            .map(|text| format!("{}{}", prefix, text.trim())),
+                    );
+                }
+            }
+        }
+        if !lines.is_empty() {
+            sections.push(lines.join("\n"));
+        }
+    }
+    sections.join("\n\n")
+}
+
+impl ApiResponse {
+    /// Parses the product detail response for `ean`; the API answers with an
+    /// empty object for unknown products.
+    pub fn parse(value: Value, ean: &str) -> Result<Self, Error> {
+        match &value {
+            Value::Object(map) => {
+                if map.is_empty() {
+                    Err(Error::NotFound(ean.to_string()))
+                } else {
+                    Ok(serde_json::from_value(value)?)
+                }
+            }
+            _ => Err(Error::Parse("Unexpected API response type".to_string())),
+        }
+    }
+}
+
+fn deserialize_ean<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Value = Deserialize::deserialize(deserializer)?;
+    match value {
+        Value::Number(num) => Ok(num.to_string()),
+        _ => Err(serde::de::Error::custom("EAN nie jest liczbą")),
+    }
+}
+
+fn deserialize_image<'de, D>(deserializer: D) -> Result<Vec<Image>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Value = Deserialize::deserialize(deserializer)?;
+    match value {
+        Value::Array(map) => Ok(map
+            .iter()
+            .filter_map(|item| item["src"].as_str())
+            .map(|src| Image {
+                src: src.to_string(),
+            })
+            .collect()),
+        _ => Err(serde::de::Error::custom("Unexpected image field type")),
+    }
+}
+
+/// Reads the image URLs of a product, also accepting the single `image_url`
+/// string stored by older versions.
+fn deserialize_image_urls<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Value = Deserialize::deserialize(deserializer)?;
+    match value {
+        Value::String(url) if url.is_empty() => Ok(Vec::new()),
+        Value::String(url) => Ok(vec![url]),
+        Value::Array(_) => serde_json::from_value(value).map_err(serde::de::Error::custom),
+        Value::Null => Ok(Vec::new()),
+        _ => Err(serde::de::Error::custom("Unexpected image_urls field type")),
+    }
+}
+
+impl From<ApiResponse> for Product {
+    /// Converts the API payload into a German storefront product without
+    /// downloading its image; see [`crate::lookup::fetch_product_info`] for
+    /// the full lookup.
+    fn from(api_response: ApiResponse) -> Self {
+        let price = Money::parse(&api_response.price.price).unwrap_or(Money::ZERO);
+        let (size, unit_price) = api_response
+            .price
+            .infos
+            .first()
+            .map_or((None, None), |info| parse_price_info(info));
+
+        Product {
+            ean: api_response.gtin.to_string(),
+            name: api_response.title.headline,
+            country: Country::default(),
+            price,
+            quantity: 0,
+            brand: api_response.brand.map(|brand| brand.name),
+            size,
+            unit_price,
+            description: description_text(&api_response.description_groups),
+            image_urls: api_response
+                .images
+                .into_iter()
+                .map(|image| image.src)
+                .filter(|src| !src.is_empty())
+                .collect(),
+            image: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn unit_price(price: i64, unit: &str) -> Option<UnitPrice> {
+        Some(UnitPrice {
+            price: Money::from_minor(price),
+            unit: unit.to_string(),
+        })
+    }
+
+    #[test]
+    fn price_info_is_read_in_every_storefront_format() {
+        let cases = [
+            ("0,3 l (6,50 € je 1 l)", "0,3 l", unit_price(650, "1 l")),
+            (
+                "0,3 l (216,33 Kč za 1 l)",
+                "0,3 l",
+                unit_price(21633, "1 l"),
+            ),
+            ("0,3 l (8,30 € za 1 l)", "0,3 l", unit_price(830, "1 l")),
+            (
+                "0,3 l (2\u{a0}163,33 Ft / 1 l)",
+                "0,3 l",
+                unit_price(216333, "1 l"),
+            ),
+            ("0,3 l (33,30 Lei pe 1 l)", "0,3 l", unit_price(3330, "1 l")),
+            ("0,3 l (12,99 лв. за 1 л)", "0,3 l", unit_price(1299, "1 л")),
+            ("250 ml (1 l = 7,96 €)", "250 ml", unit_price(796, "1 l")),
+        ];
+        for (info, size, expected) in cases {
+            assert_eq!(
+                parse_price_info(info),
+                (Some(size.to_string()), expected),
+                "{}",
+                info
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_base_price_is_kept_as_printed() {
+        assert_eq!(
+            parse_price_info("0,3 l (cena za litr)"),
+            (Some("0,3 l (cena za litr)".to_string()), None)
+        );
+        assert_eq!(
+            parse_price_info("50 Stück"),
+            (Some("50 Stück".to_string()), None)
+        );
+        assert_eq!(parse_price_info(" "), (None, None));
+    }
+
+    #[test]
+    fn description_joins_headers_texts_and_bullets() {
+        let groups: Vec<DescriptionGroup> = serde_json::from_value(json!([
+            {
+                "header": "Produktbeschreibung",
+                "contentBlock": [{"texts": [" Mild "]}, {"bulletpoints": ["a", "b"]}]
+            },
+            {"header": "", "contentBlock": [{"table": []}]},
+            {"contentBlock": [{"texts": ["Hinweis"]}]}
+        ]))
+        .unwrap();
+        assert_eq!(
+            description_text(&groups),
+            "Produktbeschreibung\nMild\n• a\n• b\n\nHinweis"
+        );
+    }
+}
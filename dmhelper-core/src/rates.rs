@@ -0,0 +1,64 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use crate::{
+    country::Currency,
+    error::{self, Error, Result},
+    money::Rate,
+    pricing::{ExchangeRates, PublishedRate},
+    storage,
+};
+
+#[derive(Deserialize, Debug)]
+struct NbpResponse {
+    rates: Vec<NbpTableRate>,
+}
+
+#[derive(Deserialize, Debug)]
+struct NbpTableRate {
+    #[serde(rename = "effectiveDate")]
+    effective_date: String,
+    mid: f64,
+}
+
+/// Fetches the current mid rate for `currency` from NBP table A.
+pub fn fetch_nbp_rate(currency: Currency) -> Result<PublishedRate> {
+    let url = format!(
+        "https://api.nbp.pl/api/exchangerates/rates/A/{}/?format=json",
+        currency.code()
+    );
+    let response = error::check_status(reqwest::blocking::get(&url)?)?;
+    let nbp_response: NbpResponse = response.json()?;
+    match nbp_response.rates.into_iter().last() {
+        Some(rate) => Ok(PublishedRate {
+            mid: Rate::from_f64(rate.mid),
+            effective_date: rate.effective_date,
+        }),
+        None => Err(Error::Parse(format!("NBP: brak kursu dla {}", currency))),
+    }
+}
+
+/// Fetches the NBP mid rate of every storefront currency.
+///
+/// Each currency is fetched on its own, so one failing does not lose the
+/// rates that were fetched.
+pub fn fetch_nbp_rates() -> Vec<(Currency, Result<PublishedRate>)> {
+    Currency::ALL
+        .into_iter()
+        .map(|currency| (currency, fetch_nbp_rate(currency)))
+        .collect()
+}
+
+/// File the last known rates are kept in.
+pub fn rates_path() -> PathBuf {
+    storage::data_dir().join("rates.json")
+}
+
+/// Loads the last known rates, or empty rates if none were saved yet.
+pub fn load_rates() -> Result<ExchangeRates> {
+    Ok(storage::load_json(&rates_path())?.unwrap_or_default())
+}
+
+pub fn save_rates(rates: &ExchangeRates) -> Result<()> {
+    storage::save_json(&rates_path(), rates)
+}
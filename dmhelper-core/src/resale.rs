@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cart::Cart,
+    money::Money,
+    pricing::{self, PlnRounding},
+};
+
+/// How the trip cost of a cart is shared out between its lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TripCostSplit {
+    /// In proportion to each line's PLN value.
+    #[default]
+    ByValue,
+    /// In proportion to each line's number of items.
+    ByItemCount,
+}
+
+impl TripCostSplit {
+    pub const ALL: [TripCostSplit; 2] = [TripCostSplit::ByValue, TripCostSplit::ByItemCount];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TripCostSplit::ByValue => "według wartości",
+            TripCostSplit::ByItemCount => "według liczby sztuk",
+        }
+    }
+}
+
+/// How selling prices are built on top of what the products cost us.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResaleRules {
+    /// Margin on the PLN price of the products, in hundredths of a percent
+    /// (`1250` is 12.5%).
+    pub margin_basis_points: i64,
+    /// Handling fee in PLN added for every item sold.
+    pub fee_per_item: Money,
+    pub trip_cost_split: TripCostSplit,
+}
+
+/// Cost and selling price of one cart line, all in PLN.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResaleLine {
+    /// The products converted to PLN.
+    pub goods: Money,
+    /// This line's part of the cart's trip cost.
+    pub trip_share: Money,
+    pub margin: Money,
+    pub fee: Money,
+    pub quantity: i32,
+}
+
+impl ResaleLine {
+    /// What the line costs us: the products plus its part of the trip.
+    pub fn cost(&self) -> Money {
+        self.goods + self.trip_share
+    }
+
+    /// What the line is sold for.
+    pub fn price(&self) -> Money {
+        self.cost() + self.margin + self.fee
+    }
+
+    /// Selling price of one item, rounded to grosze with `rounding`.
+    pub fn unit_price(&self, rounding: PlnRounding) -> Money {
+        self.price().per_unit(self.quantity, rounding.mode)
+    }
+}
+
+/// Cost and selling price of every line of a cart, see [`quote`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResaleQuote {
+    /// One entry per cart line, in cart order.
+    pub lines: Vec<ResaleLine>,
+}
+
+impl ResaleQuote {
+    pub fn total_cost(&self) -> Money {
+        self.lines.iter().map(ResaleLine::cost).sum()
+    }
+
+    pub fn total_price(&self) -> Money {
+        self.lines.iter().map(ResaleLine::price).sum()
+    }
+
+    /// What is left after paying for the products and the trip.
+    pub fn profit(&self) -> Money {
+        self.total_price() - self.total_cost()
+    }
+}
+
+/// Applies `rules` to `cart`: every line is converted to PLN at its rate,
+/// given its part of the cart's trip cost, a margin on its PLN value and a
+/// handling fee per item.
+///
+/// Lines are always rounded on their own here, since each one is priced
+/// separately for the buyer.
+pub fn quote(cart: &Cart, rounding: PlnRounding, rules: &ResaleRules) -> ResaleQuote {
+    let goods: Vec<Money> = cart
+        .items()
+        .iter()
+        .map(|line| pricing::to_pln(line.total(), line.exchange_rate, rounding.mode))
+        .collect();
+    let weights: Vec<i64> = match rules.trip_cost_split {
+        TripCostSplit::ByValue => goods.iter().map(|amount| amount.minor()).collect(),
+        TripCostSplit::ByItemCount => cart
+            .items()
+            .iter()
+            .map(|line| line.product.quantity as i64)
+            .collect(),
+    };
+    let trip_shares = cart.trip_cost.allocate(&weights);
+    let lines = cart
+        .items()
+        .iter()
+        .zip(goods)
+        .zip(trip_shares)
+        .map(|((line, goods), trip_share)| ResaleLine {
+            goods,
+            trip_share,
+            margin: goods.percent(rules.margin_basis_points, rounding.mode),
+            fee: rules.fee_per_item.times(line.product.quantity),
+            quantity: line.product.quantity,
+        })
+        .collect();
+    ResaleQuote { lines }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        money::{Rate, RoundingMode},
+        product::test_product,
+    };
+
+    /// Two lines worth 23.40 PLN each: 3 items at 4.00 and 1 item at 12.00.
+    fn cart(trip_cost: Money) -> Cart {
+        let mut cart = Cart::new();
+        cart.add(test_product("1111111111111", 3), Rate::from_f64(4.0));
+        cart.add(test_product("2222222222222", 1), Rate::from_f64(12.0));
+        cart.trip_cost = trip_cost;
+        cart
+    }
+
+    fn trip_shares(quote: &ResaleQuote) -> Vec<Money> {
+        quote.lines.iter().map(|line| line.trip_share).collect()
+    }
+
+    #[test]
+    fn trip_cost_split_by_value_gives_the_odd_grosz_to_the_first_line() {
+        let quote = quote(
+            &cart(Money::from_minor(1001)),
+            PlnRounding::default(),
+            &ResaleRules::default(),
+        );
+        assert_eq!(
+            trip_shares(&quote),
+            [Money::from_minor(501), Money::from_minor(500)]
+        );
+        assert_eq!(quote.total_cost(), Money::from_minor(2340 * 2 + 1001));
+        assert_eq!(quote.profit(), Money::ZERO);
+    }
+
+    #[test]
+    fn trip_cost_split_by_item_count() {
+        let rules = ResaleRules {
+            trip_cost_split: TripCostSplit::ByItemCount,
+            ..ResaleRules::default()
+        };
+        let quote = quote(
+            &cart(Money::from_minor(1001)),
+            PlnRounding::default(),
+            &rules,
+        );
+        assert_eq!(
+            trip_shares(&quote),
+            [Money::from_minor(751), Money::from_minor(250)]
+        );
+    }
+
+    #[test]
+    fn margin_and_fee_per_item() {
+        let rules = ResaleRules {
+            margin_basis_points: 1250,
+            fee_per_item: Money::from_minor(50),
+            trip_cost_split: TripCostSplit::ByValue,
+        };
+        let rounding = PlnRounding::default();
+        let quote = quote(&cart(Money::ZERO), rounding, &rules);
+        let line = quote.lines[0];
+        // 12.5% of 23.40 is 2.925.
+        assert_eq!(line.margin, Money::from_minor(293));
+        assert_eq!(line.fee, Money::from_minor(150));
+        assert_eq!(line.price(), Money::from_minor(2340 + 293 + 150));
+        // 27.83 / 3 = 9.2766...
+        assert_eq!(line.unit_price(rounding), Money::from_minor(928));
+        assert_eq!(quote.lines[1].fee, Money::from_minor(50));
+        assert_eq!(quote.profit(), Money::from_minor(293 * 2 + 150 + 50));
+    }
+
+    #[test]
+    fn margin_follows_the_rounding_mode() {
+        let rules = ResaleRules {
+            margin_basis_points: 1250,
+            ..ResaleRules::default()
+        };
+        let rounding = PlnRounding {
+            mode: RoundingMode::Down,
+            per_line: true,
+        };
+        let quote = quote(&cart(Money::ZERO), rounding, &rules);
+        assert_eq!(quote.lines[0].margin, Money::from_minor(292));
+    }
+}
@@ -0,0 +1,170 @@
+use serde_json::Value;
+
+use crate::{country::Country, error::Result, money::Money, product};
+
+/// At most this many search results are kept.
+pub const MAX_RESULTS: usize = 30;
+
+/// Transformation the dm image service applies to result thumbnails.
+const THUMBNAIL_TRANSFORMATIONS: &str = "f_auto,q_auto,c_fit,h_120,w_120";
+
+/// A product found by a keyword search; look it up by `ean` for the full
+/// product.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub ean: String,
+    pub name: String,
+    pub brand: Option<String>,
+    pub country: Country,
+    /// Price in the storefront's currency as listed in the results.
+    pub price: Money,
+    pub thumbnail_url: Option<String>,
+    /// Encoded thumbnail bytes, if it could be fetched.
+    pub thumbnail: Option<Vec<u8>>,
+}
+
+/// Whether every word of `query` appears in `text`, ignoring case.
+pub fn matches_query(text: &str, query: &str) -> bool {
+    let text = text.to_lowercase();
+    query
+        .split_whitespace()
+        .all(|word| text.contains(&word.to_lowercase()))
+}
+
+/// Reads the products of a dm product search response.
+///
+/// Entries without a GTIN or a title are skipped; prices may be numbers,
+/// strings such as `"1,95 €"` or objects with a `value`.
+pub fn parse_search_response(value: &Value, country: Country) -> Result<Vec<SearchHit>> {
+    let products = value["products"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    Ok(products
+        .iter()
+        .filter_map(|product| parse_hit(product, country))
+        .take(MAX_RESULTS)
+        .collect())
+}
+
+fn parse_hit(product: &Value, country: Country) -> Option<SearchHit> {
+    let ean = match &product["gtin"] {
+        Value::Number(number) => number.to_string(),
+        Value::String(text) if !text.is_empty() => text.clone(),
+        _ => return None,
+    };
+    let name = product["title"]
+        .as_str()
+        .or_else(|| product["title"]["headline"].as_str())?
+        .to_string();
+    let brand = product["brandName"]
+        .as_str()
+        .or_else(|| product["brand"]["name"].as_str())
+        .map(str::to_string);
+    let thumbnail_url = product["imageUrlTemplates"][0]
+        .as_str()
+        .or_else(|| product["images"][0]["src"].as_str())
+        .map(|template| template.replace("{transformations}", THUMBNAIL_TRANSFORMATIONS));
+    Some(SearchHit {
+        ean,
+        name,
+        brand,
+        country,
+        price: parse_price(&product["price"]).unwrap_or(Money::ZERO),
+        thumbnail_url,
+        thumbnail: None,
+    })
+}
+
+fn parse_price(price: &Value) -> Option<Money> {
+    match price {
+        Value::Number(number) => Some(Money::from_f64(number.as_f64()?)),
+        Value::String(text) => product::parse_amount(text),
+        Value::Object(_) => parse_price(&price["value"]).or_else(|| parse_price(&price["price"])),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn prices_are_read_from_numbers_strings_and_objects() {
+        let cases = [
+            (json!(1.95), Some(195)),
+            (json!("1,95 €"), Some(195)),
+            (json!("2\u{a0}163,33 Ft"), Some(216333)),
+            (json!({"value": 3.45}), Some(345)),
+            (json!({"price": "12,99 лв."}), Some(1299)),
+            (json!({"price": "12,99"}), Some(1299)),
+            (json!("gratis"), None),
+            (json!(null), None),
+        ];
+        for (price, expected) in cases {
+            assert_eq!(
+                parse_price(&price),
+                expected.map(Money::from_minor),
+                "{}",
+                price
+            );
+        }
+    }
+
+    #[test]
+    fn hits_are_read_from_either_product_shape() {
+        let response = json!({"products": [
+            {
+                "gtin": 4058172936760u64,
+                "title": "Shampoo Mild",
+                "brandName": "Balea",
+                "price": "1,95 €",
+                "imageUrlTemplates": ["https://media.dm/{transformations}/shampoo.jpg"]
+            },
+            {
+                "gtin": "4066447225451",
+                "title": {"headline": "Duschgel"},
+                "brand": {"name": "Balea"},
+                "price": {"value": 0.95},
+                "images": [{"src": "https://media.dm/duschgel.jpg"}]
+            },
+            {"gtin": "", "title": "Ohne GTIN"},
+            {"gtin": 4066447225468u64}
+        ]});
+        let hits = parse_search_response(&response, Country::Cz).unwrap();
+        assert_eq!(hits.len(), 2);
+
+        assert_eq!(hits[0].ean, "4058172936760");
+        assert_eq!(hits[0].name, "Shampoo Mild");
+        assert_eq!(hits[0].brand.as_deref(), Some("Balea"));
+        assert_eq!(hits[0].country, Country::Cz);
+        assert_eq!(hits[0].price, Money::from_minor(195));
+        assert_eq!(
+            hits[0].thumbnail_url.as_deref(),
+            Some("https://media.dm/f_auto,q_auto,c_fit,h_120,w_120/shampoo.jpg")
+        );
+
+        assert_eq!(hits[1].ean, "4066447225451");
+        assert_eq!(hits[1].name, "Duschgel");
+        assert_eq!(hits[1].price, Money::from_minor(95));
+        assert_eq!(
+            hits[1].thumbnail_url.as_deref(),
+            Some("https://media.dm/duschgel.jpg")
+        );
+    }
+
+    #[test]
+    fn results_are_capped_and_missing_products_are_empty() {
+        let products: Vec<Value> = (0..MAX_RESULTS + 5)
+            .map(|gtin| json!({"gtin": gtin, "title": "Produkt"}))
+            .collect();
+        let hits = parse_search_response(&json!({ "products": products }), Country::De).unwrap();
+        assert_eq!(hits.len(), MAX_RESULTS);
+        assert_eq!(hits[0].price, Money::ZERO);
+
+        assert!(parse_search_response(&json!({}), Country::De)
+            .unwrap()
+            .is_empty());
+    }
+}
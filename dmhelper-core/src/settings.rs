@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::{error::Result, pricing::PlnRounding, resale::ResaleRules, storage};
+
+/// User preferences kept between sessions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub rounding: PlnRounding,
+    pub resale: ResaleRules,
+}
+
+impl Settings {
+    /// Loads the saved settings, or the defaults if none were saved yet.
+    pub fn load() -> Result<Self> {
+        Ok(storage::load_json(&settings_path())?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        storage::save_json(&settings_path(), self)
+    }
+}
+
+/// File the settings are kept in.
+pub fn settings_path() -> PathBuf {
+    storage::data_dir().join("settings.json")
+}
@@ -0,0 +1,227 @@
+use reqwest::Url;
+use serde_json::Value;
+use std::{fs, io, path::PathBuf};
+
+use crate::{
+    country::Country,
+    error::{self, Error, Result},
+    lookup,
+    product::{ApiResponse, Product},
+    search::{self, SearchHit},
+};
+
+/// Somewhere products can be looked up by GTIN.
+///
+/// Sources are shared with the background lookup worker, hence `Send + Sync`.
+pub trait ProductSource: Send + Sync {
+    /// Returns the product for `ean` in the `country` storefront without its
+    /// image.
+    fn fetch_product(&self, country: Country, ean: &str) -> Result<Product>;
+
+    /// Returns the encoded bytes of image number `index` (`0` is the main
+    /// image) of a product returned by [`ProductSource::fetch_product`].
+    fn fetch_image(&self, product: &Product, index: usize) -> Result<Vec<u8>>;
+
+    /// Finds products matching the keywords in `query` in the `country`
+    /// storefront, at most [`search::MAX_RESULTS`].
+    fn search(&self, country: Country, query: &str) -> Result<Vec<SearchHit>>;
+
+    /// Returns the encoded thumbnail of a hit returned by
+    /// [`ProductSource::search`].
+    fn fetch_thumbnail(&self, hit: &SearchHit) -> Result<Vec<u8>>;
+}
+
+fn parse_product(value: Value, country: Country, ean: &str) -> Result<Product> {
+    let mut product = Product::from(ApiResponse::parse(value, ean)?);
+    product.country = country;
+    Ok(product)
+}
+
+fn no_image(product: &Product, index: usize) -> Error {
+    Error::Image(format!(
+        "Brak zdjęcia {} produktu {}",
+        index + 1,
+        product.ean
+    ))
+}
+
+/// The dm.de product API.
+#[derive(Debug, Clone, Default)]
+pub struct DmSource;
+
+impl DmSource {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ProductSource for DmSource {
+    fn fetch_product(&self, country: Country, ean: &str) -> Result<Product> {
+        let url = format!(
+            "https://products.dm.de/product/{}/products/detail/gtin/{}",
+            country.code(),
+            ean
+        );
+        let response = reqwest::blocking::get(&url)?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(Error::NotFound(ean.to_string()));
+        }
+        let resp: Value = error::check_status(response)?.json()?;
+        parse_product(resp, country, ean)
+    }
+
+    fn fetch_image(&self, product: &Product, index: usize) -> Result<Vec<u8>> {
+        match product.image_urls.get(index) {
+            Some(url) => lookup::download_image(url),
+            None => Err(no_image(product, index)),
+        }
+    }
+
+    fn search(&self, country: Country, query: &str) -> Result<Vec<SearchHit>> {
+        let url = Url::parse_with_params(
+            &format!(
+                "https://product-search.services.dmtech.com/{}/search/static",
+                country.code().to_lowercase()
+            ),
+            &[
+                ("query", query),
+                ("searchType", "product"),
+                ("pageSize", &search::MAX_RESULTS.to_string()),
+            ],
+        )
+        .map_err(|e| Error::Parse(e.to_string()))?;
+        let response = error::check_status(reqwest::blocking::get(url)?)?;
+        let resp: Value = response.json()?;
+        search::parse_search_response(&resp, country)
+    }
+
+    fn fetch_thumbnail(&self, hit: &SearchHit) -> Result<Vec<u8>> {
+        match &hit.thumbnail_url {
+            Some(url) => lookup::download_image(url),
+            None => Err(Error::Image(format!("Brak zdjęcia produktu {}", hit.ean))),
+        }
+    }
+}
+
+/// Serves products from a directory of saved API responses, for offline work
+/// and tests.
+///
+/// A product with GTIN `4058172936760` is read from `AT/4058172936760.json`
+/// for the Austrian storefront, falling back to `4058172936760.json` for any
+/// country. Its images are read from the files named by the product's image
+/// `src`s (relative to the directory), falling back to `<gtin>.jpg` or
+/// `<gtin>.png` for the main image and `<gtin>-<n>.jpg` or `<gtin>-<n>.png`
+/// for image number `n`.
+#[derive(Debug, Clone)]
+pub struct FixtureSource {
+    dir: PathBuf,
+}
+
+impl FixtureSource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl ProductSource for FixtureSource {
+    fn fetch_product(&self, country: Country, ean: &str) -> Result<Product> {
+        let file_name = format!("{}.json", ean);
+        let mut path = self.dir.join(country.code()).join(&file_name);
+        if !path.is_file() {
+            path = self.dir.join(&file_name);
+        }
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(Error::NotFound(ean.to_string()));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let resp: Value = serde_json::from_str(&contents)?;
+        parse_product(resp, country, ean)
+    }
+
+    fn fetch_image(&self, product: &Product, index: usize) -> Result<Vec<u8>> {
+        let stem = match index {
+            0 => product.ean.clone(),
+            _ => format!("{}-{}", product.ean, index),
+        };
+        let mut candidates: Vec<PathBuf> = product
+            .image_urls
+            .get(index)
+            .map(|url| self.dir.join(url))
+            .into_iter()
+            .collect();
+        candidates.push(self.dir.join(format!("{}.jpg", stem)));
+        candidates.push(self.dir.join(format!("{}.png", stem)));
+        for path in candidates.iter() {
+            if path.is_file() {
+                return Ok(fs::read(path)?);
+            }
+        }
+        Err(no_image(product, index))
+    }
+
+    /// Matches the keywords against the brand and name of every saved
+    /// response for `country` and of the shared ones.
+    fn search(&self, country: Country, query: &str) -> Result<Vec<SearchHit>> {
+        let mut hits: Vec<SearchHit> = Vec::new();
+        for dir in [self.dir.join(country.code()), self.dir.clone()] {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let mut paths: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect();
+            paths.sort();
+            for path in paths {
+                let Some(ean) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                    continue;
+                };
+                if hits.iter().any(|hit| hit.ean == ean) {
+                    continue;
+                }
+                let Ok(product) = self.fetch_product(country, ean) else {
+                    continue;
+                };
+                let text = format!(
+                    "{} {}",
+                    product.brand.as_deref().unwrap_or_default(),
+                    product.name
+                );
+                if search::matches_query(&text, query) {
+                    hits.push(SearchHit {
+                        thumbnail_url: product.image_url().map(str::to_string),
+                        ean: product.ean,
+                        name: product.name,
+                        brand: product.brand,
+                        country,
+                        price: product.price,
+                        thumbnail: None,
+                    });
+                }
+            }
+        }
+        hits.truncate(search::MAX_RESULTS);
+        Ok(hits)
+    }
+
+    fn fetch_thumbnail(&self, hit: &SearchHit) -> Result<Vec<u8>> {
+        let mut candidates: Vec<PathBuf> = hit
+            .thumbnail_url
+            .iter()
+            .map(|url| self.dir.join(url))
+            .collect();
+        candidates.push(self.dir.join(format!("{}.jpg", hit.ean)));
+        candidates.push(self.dir.join(format!("{}.png", hit.ean)));
+        for path in candidates.iter() {
+            if path.is_file() {
+                return Ok(fs::read(path)?);
+            }
+        }
+        Err(Error::Image(format!("Brak zdjęcia produktu {}", hit.ean)))
+    }
+}
@@ -0,0 +1,51 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::error::Result;
+
+/// Directory DMHelper keeps its files in.
+///
+/// `DMHELPER_DATA_DIR` wins if set; otherwise the platform's per-user data
+/// directory is used (`%APPDATA%\dmhelper`, `~/Library/Application
+/// Support/dmhelper` or `$XDG_DATA_HOME/dmhelper`, defaulting to
+/// `~/.local/share/dmhelper`).
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("DMHELPER_DATA_DIR") {
+        return PathBuf::from(dir);
+    }
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    base.unwrap_or_else(|| PathBuf::from(".")).join("dmhelper")
+}
+
+/// Reads a JSON file, returning `None` if it does not exist yet.
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Some(serde_json::from_slice(&contents)?))
+}
+
+/// Writes `value` as JSON, replacing the file only once it is fully written
+/// so a crash never leaves a truncated file behind.
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_vec(value)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
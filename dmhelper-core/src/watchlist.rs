@@ -0,0 +1,219 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    country::{Country, Currency},
+    error::Result,
+    money::Money,
+    product::Product,
+    storage,
+};
+
+/// How often products on the watchlist are looked up again while the
+/// window is open.
+pub const CHECK_INTERVAL_MINUTES: i64 = 60;
+
+/// A product waited on until its price drops to `target`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchItem {
+    pub country: Country,
+    pub ean: String,
+    pub name: String,
+    /// Price to wait for, in the storefront's currency.
+    pub target: Money,
+    pub added_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_checked: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_price: Option<Money>,
+}
+
+impl WatchItem {
+    pub fn currency(&self) -> Currency {
+        self.country.currency()
+    }
+
+    /// Whether the last price seen is at or below the target.
+    pub fn is_reached(&self) -> bool {
+        self.last_price.is_some_and(|price| price <= self.target)
+    }
+
+    /// Whether the item was not checked within [`CHECK_INTERVAL_MINUTES`]
+    /// before `now`.
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.last_checked
+            .is_none_or(|at| now - at >= Duration::minutes(CHECK_INTERVAL_MINUTES))
+    }
+
+    /// Stores the price of `product` seen at `at`; returns whether it just
+    /// dropped to or below the target, i.e. the item should alert.
+    pub fn check(&mut self, product: &Product, at: DateTime<Utc>) -> bool {
+        let was_reached = self.is_reached();
+        self.name.clone_from(&product.name);
+        self.last_checked = Some(at);
+        self.last_price = Some(product.price).filter(|price| *price > Money::ZERO);
+        self.is_reached() && !was_reached
+    }
+}
+
+/// EANs waited on for a price drop, shared by the window and the command line.
+#[derive(Debug, Clone, Default)]
+pub struct Watchlist {
+    items: Vec<WatchItem>,
+}
+
+impl Watchlist {
+    /// Loads the watchlist from `path`; a missing file is an empty list.
+    pub fn load(path: &Path) -> Result<Self> {
+        let items = storage::load_json(path)?.unwrap_or_default();
+        Ok(Self { items })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        storage::save_json(path, &self.items)
+    }
+
+    pub fn items(&self) -> &[WatchItem] {
+        &self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn position(&self, country: Country, ean: &str) -> Option<usize> {
+        self.items
+            .iter()
+            .position(|item| item.country == country && item.ean == ean)
+    }
+
+    /// Starts watching `product` for `target`, or changes the target if it
+    /// is already watched; the product's price counts as its first check.
+    pub fn watch(&mut self, product: &Product, target: Money, at: DateTime<Utc>) {
+        let index = match self.position(product.country, &product.ean) {
+            Some(index) => index,
+            None => {
+                self.items.push(WatchItem {
+                    country: product.country,
+                    ean: product.ean.clone(),
+                    name: product.name.clone(),
+                    target,
+                    added_at: at,
+                    last_checked: None,
+                    last_price: None,
+                });
+                self.items.len() - 1
+            }
+        };
+        let item = &mut self.items[index];
+        item.target = target;
+        item.check(product, at);
+    }
+
+    /// Changes the target of the item at `index`.
+    pub fn set_target(&mut self, index: usize, target: Money) -> bool {
+        match self.items.get_mut(index) {
+            Some(item) if item.target != target => {
+                item.target = target;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn remove(&mut self, country: Country, ean: &str) -> Option<WatchItem> {
+        let index = self.position(country, ean)?;
+        Some(self.items.remove(index))
+    }
+
+    /// Records the price of a looked up product if it is watched; returns
+    /// the item if the price just dropped to or below its target.
+    pub fn check(&mut self, product: &Product, at: DateTime<Utc>) -> Option<&WatchItem> {
+        let index = self.position(product.country, &product.ean)?;
+        let item = &mut self.items[index];
+        item.check(product, at).then_some(&*item)
+    }
+
+    /// Marks the item as checked at `at` without a new price, e.g. after a
+    /// failed lookup, so it is not retried right away.
+    pub fn mark_checked(&mut self, country: Country, ean: &str, at: DateTime<Utc>) {
+        if let Some(index) = self.position(country, ean) {
+            self.items[index].last_checked = Some(at);
+        }
+    }
+
+    /// Makes every item due for a check.
+    pub fn recheck_all(&mut self) {
+        for item in &mut self.items {
+            item.last_checked = None;
+        }
+    }
+
+    /// The first item not checked within [`CHECK_INTERVAL_MINUTES`].
+    pub fn next_due(&self, now: DateTime<Utc>) -> Option<&WatchItem> {
+        self.items.iter().find(|item| item.is_due(now))
+    }
+}
+
+pub fn watchlist_path() -> PathBuf {
+    storage::data_dir().join("watchlist.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::test_product;
+    use chrono::TimeZone;
+
+    fn at(minute: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 8, 0, 0).unwrap() + Duration::minutes(minute)
+    }
+
+    fn priced(price: i64) -> Product {
+        let mut product = test_product("4058172936760", 1);
+        product.price = Money::from_minor(price);
+        product
+    }
+
+    #[test]
+    fn item_alerts_once_per_drop_to_the_target() {
+        let mut list = Watchlist::default();
+        list.watch(&priced(195), Money::from_minor(150), at(0));
+        assert!(!list.items()[0].is_reached());
+
+        assert!(list.check(&priced(149), at(60)).is_some());
+        assert!(list.check(&priced(150), at(120)).is_none());
+        assert!(list.items()[0].is_reached());
+
+        assert!(list.check(&priced(195), at(180)).is_none());
+        assert!(list.check(&priced(0), at(240)).is_none());
+        assert_eq!(list.items()[0].last_price, None);
+        let item = list.check(&priced(120), at(300)).unwrap();
+        assert_eq!(item.last_price, Some(Money::from_minor(120)));
+    }
+
+    #[test]
+    fn unwatched_products_are_not_checked() {
+        let mut list = Watchlist::default();
+        list.watch(&priced(195), Money::from_minor(150), at(0));
+        let mut other = priced(100);
+        other.country = Country::At;
+        assert!(list.check(&other, at(60)).is_none());
+        assert_eq!(list.items()[0].last_checked, Some(at(0)));
+    }
+
+    #[test]
+    fn items_are_due_after_the_check_interval() {
+        let mut list = Watchlist::default();
+        list.watch(&priced(195), Money::from_minor(150), at(0));
+        let item = &list.items()[0];
+        assert!(!item.is_due(at(CHECK_INTERVAL_MINUTES - 1)));
+        assert!(item.is_due(at(CHECK_INTERVAL_MINUTES)));
+
+        list.mark_checked(Country::De, "4058172936760", at(30));
+        assert!(list.next_due(at(CHECK_INTERVAL_MINUTES)).is_none());
+        list.recheck_all();
+        assert!(list.next_due(at(31)).is_some());
+    }
+}
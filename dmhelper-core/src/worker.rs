@@ -0,0 +1,293 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    thread,
+};
+
+use crate::{
+    cache::ProductCache,
+    country::{Country, Currency},
+    error::{Error, Result},
+    pricing::PublishedRate,
+    product::Product,
+    rates,
+    search::SearchHit,
+    source::ProductSource,
+};
+
+/// Result delivered by a [`LookupTask`].
+pub type LookupResult = Result<Product>;
+
+/// A product lookup running on a background thread.
+///
+/// The task does not touch any cache; the caller is expected to store the
+/// product once [`LookupTask::poll`] returns it.
+pub struct LookupTask {
+    country: Country,
+    ean: String,
+    receiver: Receiver<LookupResult>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl LookupTask {
+    /// Starts fetching `ean` (product data and image) in the `country`
+    /// storefront from `source` on a new thread.
+    pub fn spawn(source: Arc<dyn ProductSource>, country: Country, ean: &str) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let worker_cancelled = cancelled.clone();
+        let worker_ean = ean.to_string();
+        thread::spawn(move || {
+            let result = source.fetch_product(country, &worker_ean);
+            let result = match result {
+                Ok(mut product) if !worker_cancelled.load(Ordering::Relaxed) => {
+                    product.image = source.fetch_image(&product, 0).ok();
+                    Ok(product)
+                }
+                other => other,
+            };
+            if !worker_cancelled.load(Ordering::Relaxed) {
+                let _ = sender.send(result);
+            }
+        });
+
+        Self {
+            country,
+            ean: ean.to_string(),
+            receiver,
+            cancelled,
+        }
+    }
+
+    pub fn country(&self) -> Country {
+        self.country
+    }
+
+    pub fn ean(&self) -> &str {
+        &self.ean
+    }
+
+    /// Returns the result once the worker has finished, `None` while it is
+    /// still running or after it has been cancelled.
+    pub fn poll(&self) -> Option<LookupResult> {
+        if self.is_cancelled() {
+            return None;
+        }
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(Error::Interrupted)),
+        }
+    }
+
+    /// Asks the worker to stop; a cancelled task never delivers a result.
+    ///
+    /// A request already in flight is not aborted, its result is dropped.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// One product image being fetched on a background thread.
+pub struct ImageTask {
+    receiver: Receiver<Result<Vec<u8>>>,
+}
+
+impl ImageTask {
+    /// Starts fetching image number `index` of `product` from `source`.
+    pub fn spawn(source: Arc<dyn ProductSource>, product: &Product, index: usize) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let product = product.clone();
+        thread::spawn(move || {
+            let _ = sender.send(source.fetch_image(&product, index));
+        });
+        Self { receiver }
+    }
+
+    /// Returns the encoded image once it has been fetched, `None` while the
+    /// worker is still running.
+    pub fn poll(&self) -> Option<Result<Vec<u8>>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(Error::Interrupted)),
+        }
+    }
+}
+
+/// What a [`SearchTask`] delivers, in order: the hits once, then the
+/// thumbnail of each hit that could be fetched.
+#[derive(Debug)]
+pub enum SearchUpdate {
+    /// The hits, still without thumbnails.
+    Hits(Result<Vec<SearchHit>>),
+    /// Encoded thumbnail of the hit at this index.
+    Thumbnail(usize, Vec<u8>),
+    /// Every thumbnail has been fetched or has failed to load.
+    Finished,
+}
+
+/// A keyword search running on a background thread.
+///
+/// The hits are delivered as soon as the search returns and their
+/// thumbnails follow one by one; a hit whose thumbnail fails to load is
+/// kept without one.
+pub struct SearchTask {
+    query: String,
+    receiver: Receiver<SearchUpdate>,
+    cancelled: Arc<AtomicBool>,
+    hits_delivered: bool,
+}
+
+impl SearchTask {
+    /// Starts searching the `country` storefront of `source` for `query`.
+    pub fn spawn(source: Arc<dyn ProductSource>, country: Country, query: &str) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let worker_cancelled = cancelled.clone();
+        let worker_query = query.to_string();
+        thread::spawn(move || {
+            let hits = match source.search(country, &worker_query) {
+                Ok(hits) => hits,
+                Err(e) => {
+                    let _ = sender.send(SearchUpdate::Hits(Err(e)));
+                    return;
+                }
+            };
+            if worker_cancelled.load(Ordering::Relaxed)
+                || sender.send(SearchUpdate::Hits(Ok(hits.clone()))).is_err()
+            {
+                return;
+            }
+            for (index, hit) in hits.iter().enumerate() {
+                if worker_cancelled.load(Ordering::Relaxed) {
+                    return;
+                }
+                if let Ok(thumbnail) = source.fetch_thumbnail(hit) {
+                    if sender
+                        .send(SearchUpdate::Thumbnail(index, thumbnail))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Self {
+            query: query.to_string(),
+            receiver,
+            cancelled,
+            hits_delivered: false,
+        }
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Whether [`SearchTask::poll`] has returned the hits; only thumbnails
+    /// follow.
+    pub fn hits_delivered(&self) -> bool {
+        self.hits_delivered
+    }
+
+    /// Returns the next update from the worker, `None` while it is working
+    /// on it or after the task has been cancelled.
+    pub fn poll(&mut self) -> Option<SearchUpdate> {
+        if self.is_cancelled() {
+            return None;
+        }
+        match self.receiver.try_recv() {
+            Ok(update) => {
+                self.hits_delivered |= matches!(update, SearchUpdate::Hits(_));
+                Some(update)
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) if self.hits_delivered => Some(SearchUpdate::Finished),
+            Err(TryRecvError::Disconnected) => {
+                self.hits_delivered = true;
+                Some(SearchUpdate::Hits(Err(Error::Interrupted)))
+            }
+        }
+    }
+
+    /// Asks the worker to stop; a cancelled task never delivers a result.
+    ///
+    /// A thumbnail already being fetched is not aborted, it is dropped.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Result delivered by a [`RatesTask`].
+pub type RatesResult = Result<Vec<(Currency, Result<PublishedRate>)>>;
+
+/// NBP exchange rates being fetched on a background thread.
+pub struct RatesTask {
+    receiver: Receiver<RatesResult>,
+}
+
+impl RatesTask {
+    /// Starts fetching the mid rate of every storefront currency.
+    pub fn spawn() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(Ok(rates::fetch_nbp_rates()));
+        });
+        Self { receiver }
+    }
+
+    /// Returns the rates once they have been fetched, `None` while the worker
+    /// is still running.
+    pub fn poll(&self) -> Option<RatesResult> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(Error::Interrupted)),
+        }
+    }
+}
+
+/// The product cache being written to disk on a background thread.
+pub struct CacheSaveTask {
+    receiver: Receiver<Result<()>>,
+}
+
+impl CacheSaveTask {
+    /// Starts saving `cache` to `path`.
+    pub fn spawn(cache: ProductCache, path: PathBuf) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(cache.save(&path));
+        });
+        Self { receiver }
+    }
+
+    /// Returns the outcome once the file has been written, `None` while the
+    /// worker is still running.
+    pub fn poll(&self) -> Option<Result<()>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(Error::Interrupted)),
+        }
+    }
+
+    /// Blocks until the file has been written.
+    pub fn wait(self) -> Result<()> {
+        self.receiver.recv().unwrap_or(Err(Error::Interrupted))
+    }
+}
@@ -0,0 +1,816 @@
+use chrono::{Local, Utc};
+use dmhelper_core::{
+    cache, carts, export, import, lookup, prices, pricing, rates, resale, watchlist, Budget,
+    BudgetStatus, Cart, CartList, Country, Currency, Error, Money, PriceHistory, Product,
+    ProductCache, ProductSource, Rate, Settings, WatchItem, Watchlist,
+};
+use std::{
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    /// Look up a product by EAN
+    Lookup {
+        ean: String,
+        /// dm storefront, e.g. DE, AT, CZ
+        #[structopt(long, default_value = "DE")]
+        country: Country,
+    },
+    /// Find products by name, brand or keyword
+    Search {
+        /// Words that must all appear, e.g. "balea shampoo"
+        query: Vec<String>,
+        #[structopt(long, default_value = "DE")]
+        country: Country,
+    },
+    /// Work with the carts shared with the GUI
+    Cart(CartArgs),
+    /// Show the PLN exchange rates
+    Rate {
+        /// Fetch the current rates from NBP first
+        #[structopt(long)]
+        refresh: bool,
+    },
+    /// Wait for price drops on chosen products
+    Watch(WatchCommand),
+    /// Show the recorded prices of a product, or of every product seen
+    Prices {
+        ean: Option<String>,
+        #[structopt(long, default_value = "DE")]
+        country: Country,
+    },
+}
+
+/// Parses a `--quantity` of at least one piece.
+fn parse_quantity(text: &str) -> Result<i32, String> {
+    match text.trim().parse::<i32>() {
+        Ok(quantity) if quantity >= 1 => Ok(quantity),
+        _ => Err(format!("Ilość musi być liczbą całkowitą od 1: {}", text)),
+    }
+}
+
+/// Exit code of `dmhelper watch check` when a watched price is at or below
+/// its target.
+pub const TARGET_REACHED_EXIT_CODE: i32 = 3;
+
+/// Exit code of `dmhelper watch check` when no target is reached but some
+/// products could not be looked up, e.g. because the network is down.
+pub const CHECK_FAILED_EXIT_CODE: i32 = 4;
+
+#[derive(StructOpt, Debug)]
+pub enum WatchCommand {
+    /// Watch a product until its price drops to `target` or lower
+    Add {
+        ean: String,
+        /// Price to wait for, in the storefront's currency
+        target: String,
+        #[structopt(long, default_value = "DE")]
+        country: Country,
+    },
+    /// Stop watching a product
+    Remove {
+        ean: String,
+        #[structopt(long, default_value = "DE")]
+        country: Country,
+    },
+    /// Print the watched products with their last price
+    List,
+    /// Look every watched product up again; exits with code 3 if any price
+    /// is at or below its target, otherwise with 4 if any lookup failed
+    Check,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct CartArgs {
+    /// Name of the cart to use instead of the active one
+    #[structopt(long = "cart")]
+    name: Option<String>,
+
+    #[structopt(subcommand)]
+    command: CartCommand,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum CartCommand {
+    /// Look up a product and add it to the cart
+    Add {
+        ean: String,
+        #[structopt(short, long, default_value = "1", parse(try_from_str = parse_quantity))]
+        quantity: i32,
+        #[structopt(long, default_value = "DE")]
+        country: Country,
+    },
+    /// Add every product from a list of `EAN[,quantity]` lines
+    Import {
+        /// File with the list; read from standard input if omitted
+        #[structopt(parse(from_os_str))]
+        file: Option<PathBuf>,
+        #[structopt(long, default_value = "DE")]
+        country: Country,
+        /// Only print the report, do not touch the cart
+        #[structopt(long)]
+        dry_run: bool,
+    },
+    /// Print the cart lines
+    List {
+        /// Only lines with this tag
+        #[structopt(long)]
+        tag: Option<String>,
+        /// Only lines whose name, EAN or note contains this text
+        #[structopt(long, default_value = "")]
+        filter: String,
+    },
+    /// Remove a product from the cart
+    Remove {
+        ean: String,
+        #[structopt(long, default_value = "DE")]
+        country: Country,
+    },
+    /// Assign part of a line's quantity to a person; 0 removes them
+    Assign {
+        ean: String,
+        buyer: String,
+        quantity: i32,
+        #[structopt(long, default_value = "DE")]
+        country: Country,
+    },
+    /// Set the note of a line; an empty note removes it
+    Note {
+        ean: String,
+        note: String,
+        #[structopt(long, default_value = "DE")]
+        country: Country,
+    },
+    /// Add a tag to a line
+    Tag {
+        ean: String,
+        tag: String,
+        /// Remove the tag instead
+        #[structopt(long)]
+        remove: bool,
+        #[structopt(long, default_value = "DE")]
+        country: Country,
+    },
+    /// Print what each person owes
+    Buyers {
+        /// Also write the per-person breakdown as CSV to this file
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Print our cost and the selling price of every line
+    Resale {
+        /// Set the PLN trip cost shared out between the lines first
+        #[structopt(long)]
+        trip_cost: Option<String>,
+    },
+    /// Show or set the cart budget
+    Budget {
+        /// New limit; the current budget is shown if omitted
+        limit: Option<String>,
+        /// Currency of the limit: PLN or a storefront currency
+        #[structopt(long, default_value = "PLN")]
+        currency: String,
+        /// Remove the budget
+        #[structopt(long)]
+        clear: bool,
+    },
+    /// Print the cart totals
+    Total,
+    /// Export the cart as CSV for Polish spreadsheets (`;`, decimal comma)
+    Export {
+        /// File to write; prints to standard output if omitted
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Write a printable HTML summary of the cart with product images
+    Summary {
+        /// HTML file to write
+        #[structopt(short, long, parse(from_os_str))]
+        output: PathBuf,
+        /// Also print the summary to a PDF next to the HTML file
+        #[structopt(long)]
+        pdf: bool,
+    },
+    /// List all carts; the active one is marked with `*`
+    Carts,
+}
+
+/// Runs `command` against the same data files the GUI uses.
+pub fn run(
+    command: Command,
+    source: &dyn ProductSource,
+    cached_items: &mut ProductCache,
+) -> Result<i32, Error> {
+    match command {
+        Command::Lookup { ean, country } => {
+            let product = fetch(source, country, &ean, cached_items)?;
+            let rates = rates::load_rates()?;
+            let rounding = Settings::load()?.rounding;
+            match &product.brand {
+                Some(brand) => println!(
+                    "{} {} ({}, {})",
+                    brand, product.name, product.ean, product.country
+                ),
+                None => println!("{} ({}, {})", product.name, product.ean, product.country),
+            }
+            if let Some(size) = &product.size {
+                println!("{}", size);
+            }
+            println!(
+                "{} {} = {} PLN",
+                product.price,
+                product.currency(),
+                pricing::to_pln(product.price, rates.get(product.currency()), rounding.mode)
+            );
+            if let Some(unit_price) = product.unit_price_label() {
+                println!("{}", unit_price);
+            }
+            warn_missing_rates([(product.currency(), rates.get(product.currency()))]);
+            if !product.description.is_empty() {
+                println!("\n{}", product.description);
+            }
+        }
+        Command::Search { query, country } => {
+            let query = query.join(" ");
+            if query.trim().is_empty() {
+                return Err(Error::Parse("Podaj szukane słowa".to_string()));
+            }
+            let hits = source.search(country, &query)?;
+            if hits.is_empty() {
+                println!("Nie znaleziono produktów dla „{}”", query);
+            }
+            for hit in hits {
+                let name = match &hit.brand {
+                    Some(brand) => format!("{} {}", brand, hit.name),
+                    None => hit.name,
+                };
+                println!(
+                    "{}  {:>8} {}  {}",
+                    hit.ean,
+                    hit.price,
+                    hit.country.currency(),
+                    name
+                );
+            }
+        }
+        Command::Cart(cart_args) => run_cart(cart_args, source, cached_items)?,
+        Command::Watch(command) => return run_watch(command, source, cached_items),
+        Command::Rate { refresh } => {
+            let mut exchange_rates = rates::load_rates()?;
+            if refresh {
+                let mut fetched = false;
+                let mut failed = None;
+                for (currency, rate) in rates::fetch_nbp_rates() {
+                    match rate {
+                        Ok(rate) => {
+                            exchange_rates.set_published(currency, rate);
+                            fetched = true;
+                        }
+                        Err(e) => {
+                            eprintln!("! {} {}", currency, e);
+                            failed = Some(e);
+                        }
+                    }
+                }
+                if fetched {
+                    rates::save_rates(&exchange_rates)?;
+                } else if let Some(e) = failed {
+                    return Err(e);
+                }
+            }
+            for currency in Currency::ALL {
+                let source = if exchange_rates.is_overridden(currency) {
+                    "ręcznie".to_string()
+                } else {
+                    match exchange_rates.published(currency) {
+                        Some(published) => format!("NBP {}", published.effective_date),
+                        None => "brak".to_string(),
+                    }
+                };
+                println!(
+                    "{} {} PLN ({})",
+                    currency,
+                    exchange_rates.get(currency),
+                    source
+                );
+            }
+        }
+        Command::Prices { ean, country } => {
+            let history = PriceHistory::load(&prices::price_history_path())?;
+            let mode = Settings::load()?.rounding.mode;
+            let Some(ean) = ean else {
+                for series in history.series() {
+                    if let Some(last) = series.last() {
+                        println!(
+                            "{}\t{}\t{}\t{} {}\t{}",
+                            series.ean,
+                            series.country,
+                            series.name,
+                            last.price,
+                            series.currency(),
+                            last.at.with_timezone(&Local).format("%d.%m.%Y")
+                        );
+                    }
+                }
+                return Ok(0);
+            };
+            let series = history.get(country, &ean).ok_or(Error::NotFound(ean))?;
+            println!("{} ({}, {})", series.name, series.ean, series.country);
+            for observation in &series.observations {
+                println!(
+                    "{}\t{} {}\t{} PLN",
+                    observation
+                        .at
+                        .with_timezone(&Local)
+                        .format("%d.%m.%Y %H:%M"),
+                    observation.price,
+                    series.currency(),
+                    observation.pln(mode)
+                );
+            }
+            let currency = series.currency();
+            if let (Some(min), Some(max)) = (series.min(), series.max()) {
+                println!(
+                    "min {} {} ({}), max {} {} ({})",
+                    min.price,
+                    currency,
+                    min.at.with_timezone(&Local).format("%d.%m.%Y"),
+                    max.price,
+                    currency,
+                    max.at.with_timezone(&Local).format("%d.%m.%Y")
+                );
+            }
+            if let Some((before, after)) = series.last_change() {
+                println!(
+                    "ostatnia zmiana {}: {} -> {} {}",
+                    after.at.with_timezone(&Local).format("%d.%m.%Y"),
+                    before.price,
+                    after.price,
+                    currency
+                );
+            }
+        }
+    }
+    Ok(0)
+}
+
+fn run_watch(
+    command: WatchCommand,
+    source: &dyn ProductSource,
+    cached_items: &mut ProductCache,
+) -> Result<i32, Error> {
+    let path = watchlist::watchlist_path();
+    let mut watchlist = Watchlist::load(&path)?;
+    match command {
+        WatchCommand::Add {
+            ean,
+            target,
+            country,
+        } => {
+            let target = Money::parse(&target)
+                .filter(|amount| *amount > Money::ZERO)
+                .ok_or_else(|| Error::Parse(format!("Niepoprawna kwota: {}", target)))?;
+            let product = fetch(source, country, &ean, cached_items)?;
+            watchlist.watch(&product, target, Utc::now());
+            watchlist.save(&path)?;
+            println!(
+                "Obserwowane: {} ({} {}, cel {} {})",
+                product.name,
+                product.price,
+                product.currency(),
+                target,
+                product.currency()
+            );
+        }
+        WatchCommand::Remove { ean, country } => match watchlist.remove(country, &ean) {
+            Some(item) => {
+                watchlist.save(&path)?;
+                println!("Nie obserwujesz już {}", item.name);
+            }
+            None => return Err(Error::NotFound(ean)),
+        },
+        WatchCommand::List => {
+            for item in watchlist.items() {
+                print_watch_item(item);
+            }
+        }
+        WatchCommand::Check => {
+            let mut checked = Vec::new();
+            let mut failed = 0;
+            for item in watchlist.items() {
+                match source.fetch_product(item.country, &item.ean) {
+                    Ok(product) => checked.push(product),
+                    Err(e) => {
+                        eprintln!("! {} {}", item.ean, e);
+                        failed += 1;
+                    }
+                }
+            }
+            record_prices(&checked)?;
+            let now = Utc::now();
+            for product in &checked {
+                watchlist.check(product, now);
+            }
+            watchlist.save(&path)?;
+            for item in watchlist.items() {
+                print_watch_item(item);
+            }
+            if watchlist.items().iter().any(WatchItem::is_reached) {
+                return Ok(TARGET_REACHED_EXIT_CODE);
+            }
+            if failed > 0 {
+                eprintln!(
+                    "Nie udało się sprawdzić {} z {} produktów",
+                    failed,
+                    watchlist.items().len()
+                );
+                return Ok(CHECK_FAILED_EXIT_CODE);
+            }
+        }
+    }
+    Ok(0)
+}
+
+/// Prints a watched product with its last price, marking reached targets
+/// with `!`.
+fn print_watch_item(item: &WatchItem) {
+    let price = match item.last_price {
+        Some(price) => format!("{} {}", price, item.currency()),
+        None => "?".to_string(),
+    };
+    println!(
+        "{}\t{}\t{}\t{}\tcel {} {}\t{}",
+        if item.is_reached() { "!" } else { " " },
+        item.ean,
+        item.country,
+        item.name,
+        item.target,
+        item.currency(),
+        price
+    );
+}
+
+fn run_cart(
+    args: CartArgs,
+    source: &dyn ProductSource,
+    cached_items: &mut ProductCache,
+) -> Result<(), Error> {
+    let carts_path = carts::carts_path();
+    let mut carts = CartList::load(&carts_path)?;
+    let index = match &args.name {
+        Some(name) => carts
+            .find(name)
+            .ok_or_else(|| Error::CartNotFound(name.clone()))?,
+        None => carts.active_index(),
+    };
+    if let CartCommand::Carts = args.command {
+        for (i, cart) in carts.carts().iter().enumerate() {
+            let marker = if i == carts.active_index() { "*" } else { " " };
+            println!("{} {} ({} pozycji)", marker, cart.name, cart.items().len());
+        }
+        return Ok(());
+    }
+    let global_rates = rates::load_rates()?;
+    let cart = carts.get_mut(index).unwrap();
+    let rates = cart.rates(&global_rates).clone();
+    let shows_pln = !matches!(
+        args.command,
+        CartCommand::List { .. }
+            | CartCommand::Remove { .. }
+            | CartCommand::Assign { .. }
+            | CartCommand::Note { .. }
+            | CartCommand::Tag { .. }
+    );
+    match args.command {
+        CartCommand::Add {
+            ean,
+            quantity,
+            country,
+        } => {
+            let mut product = fetch(source, country, &ean, cached_items)?;
+            product.quantity = quantity;
+            let exchange_rate = rates.get(product.currency());
+            let name = product.name.clone();
+            if !cart.add(product, exchange_rate) {
+                return Err(Error::Parse(format!("Niepoprawna ilość: {}", quantity)));
+            }
+            println!("Dodano {} x {}", quantity, name);
+            let rounding = Settings::load()?.rounding;
+            print_budget(cart.budget_status(&rates, rounding, None));
+            carts.save(&carts_path)?;
+        }
+        CartCommand::Import {
+            file,
+            country,
+            dry_run,
+        } => {
+            let text = match file {
+                Some(path) => fs::read_to_string(path)?,
+                None => {
+                    let mut text = String::new();
+                    io::stdin().read_to_string(&mut text)?;
+                    text
+                }
+            };
+            let list = import::parse_ean_list(&text);
+            let report = import::resolve(source, country, &list, cached_items);
+            cached_items.save(&cache::cache_path())?;
+            record_prices(&report.found)?;
+            for product in &report.found {
+                println!(
+                    "+ {} x {} ({})",
+                    product.quantity, product.name, product.ean
+                );
+            }
+            for ean in &report.not_found {
+                println!("? {} nie znaleziono", ean);
+            }
+            for (ean, error) in &report.failed {
+                println!("! {} {}", ean, error);
+            }
+            for duplicate in &report.duplicates {
+                println!(
+                    "= {} połączono {} linii",
+                    duplicate.ean, duplicate.occurrences
+                );
+            }
+            for skipped in &report.skipped {
+                println!(
+                    "- linia {} pominięta: {}",
+                    skipped.line_number, skipped.text
+                );
+            }
+            if !dry_run {
+                let added = report.add_to_cart(cart, &global_rates);
+                carts.save(&carts_path)?;
+                println!("Dodano {} pozycji", added);
+            }
+        }
+        CartCommand::List { tag, filter } => {
+            for line in cart.items() {
+                if !line.matches(&filter, tag.as_deref()) {
+                    continue;
+                }
+                let product = &line.product;
+                println!(
+                    "{}\t{}\t{}\t{} x {} {}\t{}\t{}",
+                    product.ean,
+                    product.country,
+                    product.name,
+                    product.quantity,
+                    product.price,
+                    product.currency(),
+                    export::tags_label(&line.tags),
+                    line.note
+                );
+            }
+        }
+        CartCommand::Remove { ean, country } => match cart.remove(country, &ean) {
+            Some(line) => {
+                carts.save(&carts_path)?;
+                println!("Usunięto {}", line.product.name);
+            }
+            None => return Err(Error::NotFound(ean)),
+        },
+        CartCommand::Assign {
+            ean,
+            buyer,
+            quantity,
+            country,
+        } => {
+            let line = line_index(cart, country, ean)?;
+            let changed = cart.assign(line, &buyer, quantity);
+            println!("{}: {}", buyer, cart.items()[line].share_of(buyer.trim()));
+            if changed {
+                carts.save(&carts_path)?;
+            }
+        }
+        CartCommand::Note { ean, note, country } => {
+            let line = line_index(cart, country, ean)?;
+            if cart.set_note(line, &note) {
+                carts.save(&carts_path)?;
+            }
+        }
+        CartCommand::Tag {
+            ean,
+            tag,
+            remove,
+            country,
+        } => {
+            let line = line_index(cart, country, ean)?;
+            let changed = if remove {
+                cart.remove_tag(line, tag.trim())
+            } else {
+                cart.add_tag(line, &tag)
+            };
+            println!("{}", export::tags_label(&cart.items()[line].tags));
+            if changed {
+                carts.save(&carts_path)?;
+            }
+        }
+        CartCommand::Buyers { output } => {
+            let rounding = Settings::load()?.rounding;
+            for buyer_total in cart.buyer_totals(rounding) {
+                let totals: Vec<String> = buyer_total
+                    .totals
+                    .iter()
+                    .map(|(currency, total)| format!("{} {}", total, currency))
+                    .collect();
+                println!(
+                    "{}\t{}\t{} PLN",
+                    export::buyer_label(buyer_total.buyer.as_deref()),
+                    totals.join(" + "),
+                    buyer_total.total_pln
+                );
+            }
+            if let Some(path) = output {
+                export::save_csv(&path, &export::buyers_csv(cart, rounding))?;
+                println!("Zapisano {}", path.display());
+            }
+        }
+        CartCommand::Resale { trip_cost } => {
+            if let Some(trip_cost) = &trip_cost {
+                cart.trip_cost = Money::parse(trip_cost)
+                    .filter(|amount| *amount >= Money::ZERO)
+                    .ok_or_else(|| Error::Parse(format!("Niepoprawna kwota: {}", trip_cost)))?;
+            }
+            let settings = Settings::load()?;
+            let quote = resale::quote(cart, settings.rounding, &settings.resale);
+            for (line, priced) in cart.items().iter().zip(&quote.lines) {
+                println!(
+                    "{}\t{}\tkoszt {} PLN\tcena {} PLN ({} PLN/szt.)",
+                    line.product.ean,
+                    line.product.name,
+                    priced.cost(),
+                    priced.price(),
+                    priced.unit_price(settings.rounding)
+                );
+            }
+            println!(
+                "Koszt {} PLN, sprzedaż {} PLN, zysk {} PLN",
+                quote.total_cost(),
+                quote.total_price(),
+                quote.profit()
+            );
+            if trip_cost.is_some() {
+                carts.save(&carts_path)?;
+            }
+        }
+        CartCommand::Total => {
+            let rounding = Settings::load()?.rounding;
+            for (currency, total) in cart.totals() {
+                println!("{} {}", total, currency);
+            }
+            println!("{} PLN", cart.total_pln(rounding));
+            print_budget(cart.budget_status(&rates, rounding, None));
+        }
+        CartCommand::Budget {
+            limit,
+            currency,
+            clear,
+        } => {
+            if clear {
+                cart.budget = None;
+            } else if let Some(limit) = &limit {
+                let limit = Money::parse(limit)
+                    .filter(|amount| *amount >= Money::ZERO)
+                    .ok_or_else(|| Error::Parse(format!("Niepoprawna kwota: {}", limit)))?;
+                let currency =
+                    if currency.eq_ignore_ascii_case("PLN") {
+                        None
+                    } else {
+                        Some(Currency::from_code(&currency).ok_or_else(|| {
+                            Error::Parse(format!("Nieznana waluta: {}", currency))
+                        })?)
+                    };
+                cart.budget = Some(Budget { limit, currency });
+            }
+            let rounding = Settings::load()?.rounding;
+            match cart.budget_status(&rates, rounding, None) {
+                Some(status) => print_budget(Some(status)),
+                None => println!("Brak budżetu"),
+            }
+            if clear || limit.is_some() {
+                carts.save(&carts_path)?;
+            }
+        }
+        CartCommand::Export { output } => {
+            let rounding = Settings::load()?.rounding;
+            let csv = export::cart_csv(cart, rounding);
+            match output {
+                Some(path) => {
+                    export::save_csv(&path, &csv)?;
+                    println!("Zapisano {}", path.display());
+                }
+                None => print!("{}", csv),
+            }
+        }
+        CartCommand::Summary { output, pdf } => {
+            let rounding = Settings::load()?.rounding;
+            export::save_html(&output, &export::cart_summary_html(cart, rounding))?;
+            println!("Zapisano {}", output.display());
+            if pdf {
+                let pdf_path = output.with_extension("pdf");
+                export::html_to_pdf(&output, &pdf_path)?;
+                println!("Zapisano {}", pdf_path.display());
+            }
+        }
+        CartCommand::Carts => {}
+    }
+    if shows_pln {
+        let cart = &carts.carts()[index];
+        warn_missing_rates(
+            cart.items()
+                .iter()
+                .map(|line| (line.product.currency(), line.exchange_rate)),
+        );
+    }
+    Ok(())
+}
+
+/// Tells the user how to fetch the rates a PLN amount was computed without,
+/// given the currencies involved and the rate each was converted at.
+fn warn_missing_rates(rates: impl IntoIterator<Item = (Currency, Rate)>) {
+    let mut missing: Vec<Currency> = rates
+        .into_iter()
+        .filter(|(_, rate)| *rate == Rate::ZERO)
+        .map(|(currency, _)| currency)
+        .collect();
+    missing.sort_by_key(|currency| currency.code());
+    missing.dedup();
+    for currency in missing {
+        eprintln!(
+            "Uwaga: brak kursu {}, kwoty w PLN są zerowe; uruchom `dmhelper rate --refresh`",
+            currency
+        );
+    }
+}
+
+/// Prints how much of the budget is used and warns once it is exceeded.
+fn print_budget(status: Option<BudgetStatus>) {
+    let Some(status) = status else {
+        return;
+    };
+    let code = status.budget.currency_code();
+    println!(
+        "Budżet: {} / {} {}",
+        status.spent, status.budget.limit, code
+    );
+    if status.is_over() {
+        eprintln!(
+            "Uwaga: przekroczono budżet o {} {}",
+            -status.remaining(),
+            code
+        );
+    }
+    if status.rate_unknown {
+        eprintln!("Uwaga: kwoty o nieznanym kursie nie są wliczone do budżetu");
+    }
+}
+
+/// Index of the line for `ean` from the `country` storefront.
+fn line_index(cart: &Cart, country: Country, ean: String) -> Result<usize, Error> {
+    cart.items()
+        .iter()
+        .position(|line| line.product.ean == ean && line.product.country == country)
+        .ok_or(Error::NotFound(ean))
+}
+
+/// Looks a product up and keeps the shared cache file up to date.
+fn fetch(
+    source: &dyn ProductSource,
+    country: Country,
+    ean: &str,
+    cached_items: &mut ProductCache,
+) -> Result<Product, Error> {
+    let fresh = cached_items.get(country, ean).is_none();
+    let product = lookup::fetch_product_info(source, country, ean, cached_items)?;
+    cached_items.save(&cache::cache_path())?;
+    if fresh {
+        record_prices(std::slice::from_ref(&product))?;
+    }
+    Ok(product)
+}
+
+/// Adds the prices of looked up products to the price history.
+fn record_prices(products: &[Product]) -> Result<(), Error> {
+    if products.is_empty() {
+        return Ok(());
+    }
+    let path = prices::price_history_path();
+    let mut history = PriceHistory::load(&path)?;
+    let rates = rates::load_rates()?;
+    let now = Utc::now();
+    let mut changed = false;
+    for product in products {
+        changed |= history.record(product, rates.get(product.currency()), now);
+    }
+    if changed {
+        history.save(&path)?;
+    }
+    Ok(())
+}
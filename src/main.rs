@@ -1,6 +1,51 @@
+use dmhelper_core::{cache, DmSource, FixtureSource, ProductCache, ProductSource};
+use std::{path::PathBuf, process, sync::Arc};
+use structopt::StructOpt;
+
+pub mod cli;
 pub mod ui;
 
+#[derive(StructOpt, Debug)]
+#[structopt(name = "dmhelper")]
+struct Opt {
+    /// Serve products from a directory of saved API responses (`<gtin>.json`)
+    /// instead of dm.de
+    #[structopt(long, parse(from_os_str))]
+    fixtures: Option<PathBuf>,
+
+    /// Size limit of the on-disk product cache in megabytes
+    #[structopt(long, default_value = "50")]
+    cache_size_mb: usize,
+
+    /// Run a command instead of opening the window
+    #[structopt(subcommand)]
+    command: Option<cli::Command>,
+}
+
 fn main() {
+    let opt = Opt::from_args();
+    let source: Arc<dyn ProductSource> = match opt.fixtures {
+        Some(dir) => Arc::new(FixtureSource::new(dir)),
+        None => Arc::new(DmSource::new()),
+    };
+    let max_cache_bytes = opt.cache_size_mb * 1024 * 1024;
+
+    if let Some(command) = opt.command {
+        let mut cached_items = ProductCache::load(&cache::cache_path(), max_cache_bytes)
+            .unwrap_or_else(|e| {
+                eprintln!("Wczytanie cache: {}", e);
+                ProductCache::with_max_bytes(max_cache_bytes)
+            });
+        match cli::run(command, source.as_ref(), &mut cached_items) {
+            Ok(0) => return,
+            Ok(code) => process::exit(code),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_resizable(false),
         ..Default::default()
@@ -8,13 +53,16 @@ fn main() {
     let _ = eframe::run_native(
         "DMHelper",
         native_options,
-        Box::new(|cc| {
+        Box::new(move |cc| {
             cc.egui_ctx.set_style(egui::Style {
                 visuals: egui::Visuals::dark(),
                 ..egui::Style::default()
             });
             egui_extras::install_image_loaders(&cc.egui_ctx);
-            Ok(Box::new(ui::dmhelper::DMHelper::new()))
+            Ok(Box::new(ui::dmhelper::DMHelper::new(
+                source,
+                max_cache_bytes,
+            )))
         }),
     );
 }
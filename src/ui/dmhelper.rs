@@ -1,308 +1,668 @@
-use chrono::{DateTime, Duration, Utc};
-use egui::{vec2, CentralPanel, ColorImage, TopBottomPanel};
-use image::{io::Reader, DynamicImage};
-use reqwest::Url;
-use serde::{Deserialize, Deserializer};
-use serde_json::Value;
-use std::{
-    collections::HashMap,
-    error::Error,
-    io::{self, Cursor},
+use chrono::Utc;
+use dmhelper_core::{
+    cache, cart, carts, export, prices, pricing, rates, watchlist, BudgetStatus, CacheSaveTask,
+    Cart, CartList, CartOp, Country, Currency, ExchangeRates, ImportReport, LookupTask, Money,
+    PriceHistory, Product, ProductCache, ProductSource, Rate, RatesTask, RoundingMode, SearchTask,
+    Settings, Watchlist,
 };
+use egui::{CentralPanel, ColorImage, Key, KeyboardShortcut, Modifiers, TopBottomPanel};
+use image::DynamicImage;
+use std::sync::Arc;
 
-#[derive(Deserialize, Debug)]
-struct ApiResponse {
-    #[serde(deserialize_with = "deserialize_ean")]
-    gtin: String,
-    title: Title,
-    price: Price,
-    #[serde(deserialize_with = "deserialize_image")]
-    images: Vec<Image>,
-}
+use self::{gallery::Gallery, import_panel::ImportJob, search_panel::SearchRow};
+use super::status::StatusLog;
+
+mod cart_panel;
+mod gallery;
+mod import_panel;
+mod price_panel;
+mod search_panel;
+mod watch_panel;
 
-#[derive(Deserialize, Debug)]
-struct Title {
-    headline: String,
+fn image_to_color_image(image: DynamicImage) -> ColorImage {
+    let rgba = image.to_rgba8();
+    let size = [rgba.width() as usize, rgba.height() as usize];
+    ColorImage::from_rgba_unmultiplied(size, rgba.as_flat_samples().as_slice())
 }
 
-#[derive(Deserialize, Debug)]
-struct Image {
-    src: String,
+/// `spent / limit currency`, flagged when amounts without a known rate
+/// were counted as zero.
+fn budget_usage(status: &BudgetStatus) -> String {
+    let mut usage = format!(
+        "{} / {} {}",
+        status.spent,
+        status.budget.limit,
+        status.budget.currency_code()
+    );
+    if status.rate_unknown {
+        usage.push_str(" (bez kwot o nieznanym kursie)");
+    }
+    usage
 }
 
-#[derive(Deserialize, Debug)]
-struct Price {
-    price: String,
+/// Tells by how much the budget in `status` is exceeded.
+fn budget_warning(status: &BudgetStatus) -> String {
+    format!(
+        "Przekroczono budżet o {} {}",
+        -status.remaining(),
+        status.budget.currency_code()
+    )
 }
 
-#[derive(Debug, Clone)]
-struct Product {
+pub struct DMHelper {
+    source: Arc<dyn ProductSource>,
+    settings: Settings,
+    exchange_rates: ExchangeRates,
+    cached_items: ProductCache,
+    /// Set when the cache has changed since it was last handed to a save.
+    cache_changed: bool,
+    cache_save: Option<CacheSaveTask>,
+    country: Country,
     ean: String,
-    name: String,
-    price: f32,
-    quantity: i32,
-    image: Option<ColorImage>,
+    carts: CartList,
+    confirm_clear_cart: bool,
+    new_cart_name: String,
+    /// Name typed for the active cart, applied on Enter or lost focus.
+    cart_name_draft: Option<String>,
+    new_buyer_name: String,
+    new_tag: String,
+    /// Line whose note is being edited and the text typed so far.
+    note_draft: Option<(usize, String)>,
+    /// Line whose quantity is being dragged or typed and its value so far.
+    quantity_draft: Option<(usize, i32)>,
+    cart_filter: String,
+    cart_tag_filter: Option<String>,
+    product: Option<Product>,
+    gallery: Gallery,
+    lookup: Option<LookupTask>,
+    search_query: String,
+    search_task: Option<SearchTask>,
+    search_results: Vec<SearchRow>,
+    /// Search hit to add to the active cart once its lookup finishes.
+    pending_add: Option<(Country, String)>,
+    import_text: String,
+    import_job: Option<ImportJob>,
+    import_report: Option<ImportReport>,
+    rates_task: Option<RatesTask>,
+    price_history: PriceHistory,
+    watchlist: Watchlist,
+    /// Lookup of the watched product being checked.
+    watch_task: Option<LookupTask>,
+    /// Target price typed for the product in the product panel.
+    watch_target: Money,
+    /// Product shown in the price history window, if it is open.
+    price_chart: Option<(Country, String)>,
+    status: StatusLog,
 }
 
-impl TryFrom<Value> for ApiResponse {
-    type Error = &'static str;
-    fn try_from(value: Value) -> Result<Self, Self::Error> {
-        match &value {
-            Value::Object(map) => {
-                if map.is_empty() {
-                    Err("API response is an empty object")
-                } else {
-                    serde_json::from_value(value).map_err(|_| "Nie znaleziono produktu")
+impl DMHelper {
+    /// Opens the window's state, loading the saved files; a file that fails
+    /// to load is reported in the status area and replaced with an empty one.
+    pub fn new(source: Arc<dyn ProductSource>, max_cache_bytes: usize) -> Self {
+        let mut status = StatusLog::default();
+        let cached_items = ProductCache::load(&cache::cache_path(), max_cache_bytes)
+            .unwrap_or_else(|e| {
+                status.error("Wczytanie cache", e);
+                ProductCache::with_max_bytes(max_cache_bytes)
+            });
+        let carts = CartList::load(&carts::carts_path()).unwrap_or_else(|e| {
+            status.error("Wczytanie koszyków", e);
+            CartList::default()
+        });
+        let exchange_rates = rates::load_rates().unwrap_or_else(|e| {
+            status.error("Wczytanie kursów", e);
+            ExchangeRates::new()
+        });
+        let price_history = PriceHistory::load(&prices::price_history_path()).unwrap_or_else(|e| {
+            status.error("Wczytanie historii cen", e);
+            PriceHistory::default()
+        });
+        let watchlist = Watchlist::load(&watchlist::watchlist_path()).unwrap_or_else(|e| {
+            status.error("Wczytanie obserwowanych", e);
+            Watchlist::default()
+        });
+        let settings = Settings::load().unwrap_or_else(|e| {
+            status.error("Wczytanie ustawień", e);
+            Settings::default()
+        });
+        Self {
+            source,
+            settings,
+            exchange_rates,
+            cached_items,
+            cache_changed: false,
+            cache_save: None,
+            country: Country::default(),
+            ean: String::new(),
+            carts,
+            confirm_clear_cart: false,
+            new_cart_name: String::new(),
+            cart_name_draft: None,
+            new_buyer_name: String::new(),
+            new_tag: String::new(),
+            note_draft: None,
+            quantity_draft: None,
+            cart_filter: String::new(),
+            cart_tag_filter: None,
+            product: None,
+            gallery: Gallery::default(),
+            lookup: None,
+            search_query: String::new(),
+            search_task: None,
+            search_results: Vec::new(),
+            pending_add: None,
+            import_text: String::new(),
+            import_job: None,
+            import_report: None,
+            rates_task: Some(RatesTask::spawn()),
+            price_history,
+            price_chart: None,
+            watchlist,
+            watch_task: None,
+            watch_target: Money::ZERO,
+            status,
+        }
+    }
+
+    fn refresh_rates(&mut self) {
+        if self.rates_task.is_none() {
+            self.rates_task = Some(RatesTask::spawn());
+        }
+    }
+
+    fn poll_rates(&mut self) {
+        let Some(result) = self.rates_task.as_ref().and_then(|task| task.poll()) else {
+            return;
+        };
+        self.rates_task = None;
+        match result {
+            Ok(published) => {
+                let mut fetched = false;
+                for (currency, rate) in published {
+                    match rate {
+                        Ok(rate) => {
+                            self.exchange_rates.set_published(currency, rate);
+                            fetched = true;
+                        }
+                        Err(e) => self.status.error(
+                            &format!("Kurs NBP {} (używam ostatniego znanego kursu)", currency),
+                            e,
+                        ),
+                    }
+                }
+                if fetched {
+                    self.save_rates();
                 }
             }
-            _ => Err("Unexpected API response type"),
+            Err(e) => {
+                self.status
+                    .error("Kursy NBP (używam ostatniego znanego kursu)", e);
+            }
         }
     }
-}
 
-fn deserialize_ean<'de, D>(deserializer: D) -> Result<String, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let value: Value = Deserialize::deserialize(deserializer)?;
-    match value {
-        Value::Number(num) => Ok(num.to_string()),
-        _ => Err(serde::de::Error::custom("EAN nie jest liczbą")),
+    fn save_rates(&mut self) {
+        if let Err(e) = rates::save_rates(&self.exchange_rates) {
+            self.status.error("Zapis kursów", e);
+        }
     }
-}
 
-fn deserialize_image<'de, D>(deserializer: D) -> Result<Vec<Image>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let value: Value = Deserialize::deserialize(deserializer)?;
-    match value {
-        Value::Array(map) => {
-            return Ok(map
-                .iter()
-                .map(|item| Image {
-                    src: item["src"].to_string().replace("\"", ""),
-                })
-                .collect());
+    fn save_settings(&mut self) {
+        if let Err(e) = self.settings.save() {
+            self.status.error("Zapis ustawień", e);
         }
-        _ => Err(serde::de::Error::custom("Unexpected image field type")),
     }
-}
 
-fn download_image(input_url: &str) -> Result<DynamicImage, Box<dyn Error>> {
-    let url = match Url::parse(input_url) {
-        Ok(url) => url,
-        Err(e) => {
-            return Err(Box::new(e));
+    /// Writes the cache on a background thread, one save at a time; changes
+    /// made while a save runs are written once it has finished.
+    fn poll_cache_save(&mut self) {
+        if let Some(result) = self.cache_save.as_ref().and_then(|task| task.poll()) {
+            self.cache_save = None;
+            if let Err(e) = result {
+                self.status.error("Zapis cache", e);
+            }
         }
-    };
-    let response = match reqwest::blocking::get(url) {
-        Ok(response) => response,
-        Err(e) => {
-            return Err(Box::new(e));
+        if self.cache_changed && self.cache_save.is_none() {
+            self.cache_changed = false;
+            self.cache_save = Some(CacheSaveTask::spawn(
+                self.cached_items.clone(),
+                cache::cache_path(),
+            ));
         }
-    };
-    if !response.status().is_success() {
-        return Err(format!("Failed to download image: {}", response.status()).into());
     }
 
-    let bytes = response.bytes()?;
-    let cursor = Cursor::new(bytes);
-    let image = Reader::new(cursor).with_guessed_format()?.decode()?;
+    fn save_cart(&mut self) {
+        if let Err(e) = self.carts.save(&carts::carts_path()) {
+            self.status.error("Zapis koszyka", e);
+        }
+    }
 
-    Ok(image)
-}
+    fn undo_cart(&mut self) {
+        if let Some(label) = self.carts.active_mut().undo() {
+            self.status.info(format!("Cofnięto: {}", label));
+            self.save_cart();
+        }
+    }
 
-fn image_to_color_image(image: DynamicImage) -> ColorImage {
-    let rgba = image.to_rgba8();
-    let size = [rgba.width() as usize, rgba.height() as usize];
-    ColorImage::from_rgba_unmultiplied(size, rgba.as_flat_samples().as_slice())
-}
+    fn redo_cart(&mut self) {
+        if let Some(label) = self.carts.active_mut().redo() {
+            self.status.info(format!("Przywrócono: {}", label));
+            self.save_cart();
+        }
+    }
 
-impl From<ApiResponse> for Product {
-    fn from(api_response: ApiResponse) -> Self {
-        let price: f32 = api_response.price.price.parse().unwrap_or(0.0);
-        let image_url = api_response.images[0].src.to_string();
-        let url = image_url.as_str();
-        let image = match download_image(url) {
-            Ok(img) => Some(image_to_color_image(img)),
-            Err(_e) => None,
-        };
+    /// Ctrl+Z undoes and Ctrl+Shift+Z redoes the last change to the active
+    /// cart, unless a text field has the keyboard.
+    fn handle_undo_shortcuts(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        // Ctrl+Z would also match Ctrl+Shift+Z, so redo goes first.
+        let redo = KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z);
+        let undo = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+        if ctx.input_mut(|input| input.consume_shortcut(&redo)) {
+            self.redo_cart();
+        } else if ctx.input_mut(|input| input.consume_shortcut(&undo)) {
+            self.undo_cart();
+        }
+    }
 
-        Product {
-            ean: api_response.gtin.to_string(),
-            name: api_response.title.headline,
-            price,
-            quantity: 0,
-            image,
+    /// Archives the active cart and replaces it with an empty one.
+    fn start_new_cart(&mut self) {
+        if self.carts.active().is_empty() {
+            return;
+        }
+        match self.carts.active().archive(&cart::archive_dir()) {
+            Ok(path) => {
+                self.carts.restart(self.carts.active_index());
+                self.status
+                    .info(format!("Poprzedni koszyk zapisano w {}", path.display()));
+                self.save_cart();
+            }
+            Err(e) => self.status.error("Archiwizacja koszyka", e),
         }
     }
-}
 
-struct CachedItem {
-    product: Product,
-    expires_at: DateTime<Utc>,
-}
+    /// Writes the active cart as CSV into the exports directory.
+    fn export_csv(&mut self) {
+        let cart = self.carts.active();
+        let csv = export::cart_csv(cart, self.settings.rounding);
+        let path = export::export_path(cart, "csv");
+        match export::save_csv(&path, &csv) {
+            Ok(()) => self
+                .status
+                .info(format!("Wyeksportowano koszyk do {}", path.display())),
+            Err(e) => self.status.error("Eksport CSV", e),
+        }
+    }
 
-pub struct DMHelper {
-    euro_exchange_rate: f32,
-    cached_items: HashMap<String, CachedItem>,
-    ean: String,
-    cart: Vec<Product>,
-    product: Option<Product>,
-}
+    /// Writes the per-person breakdown of the active cart as CSV into the
+    /// exports directory.
+    fn export_buyers_csv(&mut self) {
+        let cart = self.carts.active();
+        let csv = export::buyers_csv(cart, self.settings.rounding);
+        let path = export::exports_dir().join(format!("{}-osoby.csv", cart.file_stem()));
+        match export::save_csv(&path, &csv) {
+            Ok(()) => self
+                .status
+                .info(format!("Wyeksportowano podział do {}", path.display())),
+            Err(e) => self.status.error("Eksport podziału", e),
+        }
+    }
 
-impl DMHelper {
-    pub fn new() -> Self {
-        return Self {
-            euro_exchange_rate: 0.0,
-            cached_items: HashMap::new(),
-            ean: String::new(),
-            cart: Vec::new(),
-            product: None,
-        };
+    /// Writes a printable HTML summary of the active cart into the exports
+    /// directory and, if `pdf` is set, prints it to PDF as well.
+    fn export_summary(&mut self, pdf: bool) {
+        let cart = self.carts.active();
+        let html = export::cart_summary_html(cart, self.settings.rounding);
+        let html_path = export::export_path(cart, "html");
+        if let Err(e) = export::save_html(&html_path, &html) {
+            self.status.error("Zapis podsumowania", e);
+            return;
+        }
+        if !pdf {
+            self.status
+                .info(format!("Zapisano podsumowanie w {}", html_path.display()));
+            return;
+        }
+        let pdf_path = html_path.with_extension("pdf");
+        match export::html_to_pdf(&html_path, &pdf_path) {
+            Ok(()) => self
+                .status
+                .info(format!("Zapisano podsumowanie w {}", pdf_path.display())),
+            Err(e) => self.status.error("Podsumowanie PDF", e),
+        }
     }
 
-    fn fetch_product_info(
-        ean: &str,
-        cache: &mut HashMap<String, CachedItem>,
-    ) -> Result<Product, Box<dyn std::error::Error>> {
-        let now = Utc::now();
-        if let Some(cached_item) = cache.get(ean) {
-            if cached_item.expires_at > now {
-                return Ok(cached_item.product.clone());
+    /// Keeps a copy of a cart removed from the switcher, unless it was empty.
+    fn archive_removed_cart(&mut self, removed: Cart) {
+        if removed.is_empty() {
+            return;
+        }
+        match removed.archive(&cart::archive_dir()) {
+            Ok(path) => self
+                .status
+                .info(format!("Usunięty koszyk zapisano w {}", path.display())),
+            Err(e) => self.status.error("Archiwizacja koszyka", e),
+        }
+    }
+
+    fn start_lookup(&mut self, ctx: &egui::Context) {
+        let ean = self.ean.trim().to_string();
+        if ean.is_empty() {
+            return;
+        }
+        if let Some(task) = self.lookup.take() {
+            task.cancel();
+        }
+        match self.cached_items.get(self.country, &ean) {
+            Some(product) => self.show_product(ctx, product),
+            None => self.lookup = Some(LookupTask::spawn(self.source.clone(), self.country, &ean)),
+        }
+    }
+
+    fn poll_lookup(&mut self, ctx: &egui::Context) {
+        let Some(result) = self.lookup.as_ref().and_then(|task| task.poll()) else {
+            return;
+        };
+        let task = self.lookup.take().unwrap();
+        match result {
+            Ok(product) => {
+                self.cached_items
+                    .insert(task.country(), task.ean(), product.clone());
+                self.cache_changed = true;
+                self.record_price(&product);
+                self.check_watched(ctx, &product);
+                self.show_product(ctx, product);
+            }
+            Err(e) => {
+                self.pending_add = None;
+                self.status.error("Wyszukiwanie produktu", e);
             }
         }
-        let url = format!(
-            "https://products.dm.de/product/DE/products/detail/gtin/{}",
-            ean
-        );
-        let resp: Value = reqwest::blocking::get(&url)?.json()?;
+    }
+
+    fn cancel_lookup(&mut self) {
+        if let Some(task) = self.lookup.take() {
+            task.cancel();
+        }
+        self.pending_add = None;
+    }
 
-        let api_response: Result<ApiResponse, _> = ApiResponse::try_from(resp);
-        match api_response {
-            Ok(api_response) => {
-                let product: Product = Product::from(api_response);
-                cache.insert(
-                    ean.to_string(),
-                    CachedItem {
-                        product: product.clone(),
-                        expires_at: now + Duration::minutes(30),
-                    },
-                );
-                Ok(product)
+    /// Adds the price of a freshly looked up product to the price history.
+    fn record_price(&mut self, product: &Product) {
+        let rate = self.exchange_rates.get(product.currency());
+        if self.price_history.record(product, rate, Utc::now()) {
+            if let Err(e) = self.price_history.save(&prices::price_history_path()) {
+                self.status.error("Zapis historii cen", e);
             }
-            Err(e) => Err(Box::new(io::Error::new(io::ErrorKind::Other, e))),
         }
     }
+
+    fn show_product(&mut self, ctx: &egui::Context, product: Product) {
+        self.watch_target = match self.watchlist.position(product.country, &product.ean) {
+            Some(index) => self.watchlist.items()[index].target,
+            None => product.price,
+        };
+        self.gallery = Gallery::new(ctx, &product);
+        self.product = Some(product);
+        self.add_pending_to_cart();
+    }
 }
 
 impl eframe::App for DMHelper {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_lookup(ctx);
+        self.poll_search(ctx);
+        self.poll_gallery(ctx);
+        self.poll_watchlist(ctx);
+        self.poll_import(ctx);
+        self.poll_rates();
+        self.poll_cache_save();
+        self.handle_undo_shortcuts(ctx);
+        self.show_price_window(ctx);
         TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.set_height(25.0);
                 ui.horizontal(|ui| {
                     ui.label("DMHelper");
+                    if ui.button("📈 Historia cen").clicked() {
+                        self.open_price_chart();
+                    }
                 });
             });
             ui.horizontal(|ui| {
                 ui.label("Exchange Rate:");
-                ui.add(egui::DragValue::new(&mut self.euro_exchange_rate).speed(0.01));
+                if self.rates_task.is_some() {
+                    ui.spinner();
+                } else if ui
+                    .button("NBP")
+                    .on_hover_text("Pobierz kursy z NBP")
+                    .clicked()
+                {
+                    self.refresh_rates();
+                }
+                let mut rates_changed = false;
+                for currency in Currency::ALL {
+                    ui.separator();
+                    ui.label(currency.code());
+                    let mut rate = self.exchange_rates.get(currency).to_f64();
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut rate)
+                                .speed(0.0001)
+                                .max_decimals(6),
+                        )
+                        .changed()
+                    {
+                        self.exchange_rates
+                            .set_override(currency, Rate::from_f64(rate));
+                        rates_changed = true;
+                    }
+                    if self.exchange_rates.is_overridden(currency) {
+                        if ui
+                            .small_button("ręcznie ✖")
+                            .on_hover_text("Wróć do kursu NBP")
+                            .clicked()
+                        {
+                            self.exchange_rates.clear_override(currency);
+                            rates_changed = true;
+                        }
+                    } else if let Some(published) = self.exchange_rates.published(currency) {
+                        ui.weak(published.effective_date.as_str());
+                    }
+                }
+                if rates_changed {
+                    self.save_rates();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Zaokrąglanie PLN:");
+                let mut rounding = self.settings.rounding;
+                egui::ComboBox::from_id_source("rounding_mode")
+                    .selected_text(rounding.mode.label())
+                    .show_ui(ui, |ui| {
+                        for mode in RoundingMode::ALL {
+                            ui.selectable_value(&mut rounding.mode, mode, mode.label());
+                        }
+                    });
+                ui.checkbox(&mut rounding.per_line, "każda pozycja osobno");
+                if rounding != self.settings.rounding {
+                    self.settings.rounding = rounding;
+                    self.save_settings();
+                }
             });
         });
+        if !self.status.is_empty() {
+            TopBottomPanel::bottom("status_panel")
+                .resizable(false)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical()
+                        .max_height(80.0)
+                        .show(ui, |ui| self.status.show(ui));
+                });
+        }
         CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.vertical(|ui| {
-                    ui.text_edit_singleline(&mut self.ean);
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_source("country")
+                            .selected_text(self.country.code())
+                            .width(50.0)
+                            .show_ui(ui, |ui| {
+                                for country in Country::ALL {
+                                    ui.selectable_value(&mut self.country, country, country.code());
+                                }
+                            });
+                        ui.text_edit_singleline(&mut self.ean);
+                    });
                     if ui.button("Pobierz informacje o produkcie").clicked() {
-                        match DMHelper::fetch_product_info(&self.ean.trim(), &mut self.cached_items)
-                        {
-                            Ok(product) => {
-                                self.product = Some(product.clone());
-                            }
-                            Err(e) => {
-                                ui.label(format!("Błąd: {}", e));
-                            }
+                        self.pending_add = None;
+                        self.start_lookup(ctx);
+                    }
+                    egui::CollapsingHeader::new("Szukaj po nazwie")
+                        .id_source("search")
+                        .show(ui, |ui| self.show_search_panel(ui));
+                    if let Some(task) = &self.lookup {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label(format!("Wyszukiwanie {}...", task.ean()));
+                        });
+                        if ui.button("Anuluj").clicked() {
+                            self.cancel_lookup();
                         }
                     }
                     if let Some(product) = &mut self.product {
                         ui.label(format!("Znaleziono produkt {}", product.name));
-                        ui.label(product.ean.clone());
+                        if let Some(brand) = &product.brand {
+                            ui.label(format!("Marka: {}", brand));
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label(format!("{} ({})", product.ean, product.country));
+                            if self
+                                .price_history
+                                .get(product.country, &product.ean)
+                                .is_some()
+                                && ui
+                                    .small_button("📈")
+                                    .on_hover_text("Historia ceny")
+                                    .clicked()
+                            {
+                                self.price_chart = Some((product.country, product.ean.clone()));
+                            }
+                        });
+                        let details: Vec<String> = product
+                            .size
+                            .iter()
+                            .cloned()
+                            .chain(product.unit_price_label())
+                            .collect();
+                        if !details.is_empty() {
+                            ui.label(details.join(", "));
+                        }
 
-                        if let Some(ref image) = product.image {
-                            let texture = ctx.load_texture(
-                                "product_image",
-                                image.clone(),
-                                egui::TextureOptions::default(),
-                            );
-                            ui.add(
-                                egui::Image::from_texture(&texture).max_size(vec2(100.0, 200.0)),
-                            );
-                        } else {
-                            ui.label("Failed to load image");
+                        self.gallery.show(ui);
+                        if !product.description.is_empty() {
+                            egui::CollapsingHeader::new("Opis").show(ui, |ui| {
+                                egui::ScrollArea::vertical()
+                                    .id_source("product_description")
+                                    .max_height(150.0)
+                                    .show(ui, |ui| ui.label(product.description.as_str()));
+                            });
                         }
 
                         ui.horizontal(|ui| {
                             ui.label("Ilość:");
-                            ui.add(egui::widgets::DragValue::new(&mut product.quantity).speed(1.0));
+                            ui.add(
+                                egui::widgets::DragValue::new(&mut product.quantity)
+                                    .speed(1.0)
+                                    .range(1..=i32::MAX),
+                            );
                         });
                         ui.horizontal(|ui| {
-                            ui.label(format!("Cena w EURO: {:.2}", product.price));
+                            ui.label(format!("Cena w {}: {}", product.currency(), product.price));
                             ui.label(format!(
-                                "Cena w PLN: {:.2}",
-                                product.price * self.euro_exchange_rate * product.quantity as f32
+                                "Cena w PLN: {}",
+                                pricing::to_pln(
+                                    pricing::line_total(product.price, product.quantity),
+                                    self.carts
+                                        .active()
+                                        .rates(&self.exchange_rates)
+                                        .get(product.currency()),
+                                    self.settings.rounding.mode
+                                )
                             ));
                         });
-                        if ui.button("Dodaj do koszyka").clicked() {
-                            if product.quantity == 0 {
-                                return;
+                        let cart = self.carts.active();
+                        let budget_after = cart.budget_status(
+                            cart.rates(&self.exchange_rates),
+                            self.settings.rounding,
+                            Some((
+                                pricing::line_total(product.price, product.quantity),
+                                product.currency(),
+                            )),
+                        );
+                        let over_budget = budget_after.is_some_and(|status| status.is_over());
+                        if let Some(status) = &budget_after {
+                            ui.label(format!("Po dodaniu: {}", budget_usage(status)));
+                            if over_budget {
+                                ui.colored_label(
+                                    ui.visuals().error_fg_color,
+                                    format!("⚠ {}", budget_warning(status)),
+                                );
                             }
-                            if self.cart.iter().any(|item| item.ean == product.ean) {
-                                let index = self
-                                    .cart
-                                    .iter()
-                                    .position(|item| item.ean == product.ean)
-                                    .unwrap();
-                                self.cart[index].quantity += product.quantity;
-                            } else {
-                                self.cart.push(product.clone());
+                        }
+                        let add_label = if over_budget {
+                            "Dodaj mimo przekroczenia budżetu"
+                        } else {
+                            "Dodaj do koszyka"
+                        };
+                        if ui.button(add_label).clicked() {
+                            let exchange_rate = self
+                                .carts
+                                .active()
+                                .rates(&self.exchange_rates)
+                                .get(product.currency());
+                            if self.carts.active_mut().add(product.clone(), exchange_rate) {
+                                if let Some(CartOp::Merge { .. }) =
+                                    self.carts.active().history().next_undo()
+                                {
+                                    self.status.info(format!(
+                                        "Dołożono do pozycji {} w koszyku (Ctrl+Z cofa)",
+                                        product.name
+                                    ));
+                                }
+                                self.product = None;
+                                self.gallery = Gallery::default();
+                                self.save_cart();
                             }
-                            self.product = None;
                         };
                     }
+                    self.show_watch_controls(ui);
+                    ui.separator();
+                    egui::CollapsingHeader::new("Import listy EAN")
+                        .show(ui, |ui| self.show_import_panel(ui));
+                    egui::CollapsingHeader::new(format!(
+                        "Obserwowane ({})",
+                        self.watchlist.items().len()
+                    ))
+                    .id_source("watchlist")
+                    .show(ui, |ui| self.show_watch_panel(ui));
                     ui.add_space(300.0);
                 });
                 ui.separator();
-                ui.vertical(|ui| {
-                    let total_price: f32 = self
-                        .cart
-                        .iter()
-                        .map(|item| item.price * item.quantity as f32)
-                        .sum();
-                    egui::ScrollArea::vertical()
-                        .max_height(ui.available_height() - 100.0)
-                        .max_width(ui.available_width())
-                        .auto_shrink(false)
-                        .show(ui, |ui| {
-                            for item in &self.cart {
-                                ui.horizontal(|ui| {
-                                    ui.label(item.name.to_string());
-                                    ui.label(item.quantity.to_string());
-                                    ui.label(format!("€{:.2}", item.price));
-                                });
-                                ui.separator();
-                            }
-                        });
-                    ui.label(format!("Kurs euro: {}", self.euro_exchange_rate));
-                    ui.label(format!(
-                        "\n\nSuma: €{:.2}, suma: {:.2}PLN",
-                        total_price,
-                        total_price * self.euro_exchange_rate
-                    ))
-                });
+                ui.vertical(|ui| self.show_cart_panel(ui));
             })
         });
         ctx.request_repaint();
     }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Some(task) = self.cache_save.take() {
+            let _ = task.wait();
+        }
+        if self.cache_changed {
+            let _ = self.cached_items.save(&cache::cache_path());
+        }
+    }
 }
@@ -0,0 +1,704 @@
+use dmhelper_core::{
+    export, pricing, resale, Budget, Currency, Money, Rate, ResaleRules, TripCostSplit,
+};
+use egui::Ui;
+
+use super::DMHelper;
+
+/// A change requested from the cart panel, applied once the lines are drawn.
+enum CartAction {
+    SetQuantity(usize, i32),
+    Remove(usize),
+    Undo,
+    Redo,
+    Clear,
+    MoveLine {
+        line: usize,
+        to: usize,
+    },
+    CopyLine {
+        line: usize,
+        to: usize,
+    },
+    SwitchCart(usize),
+    CreateCart,
+    RemoveCart,
+    RenameCart(String),
+    PinRates(bool),
+    SetRate(Currency, Rate),
+    ExportBuyers,
+    SetResaleRules(ResaleRules),
+    SetTripCost(Money),
+    SetBudget(Option<Budget>),
+    Assign {
+        line: usize,
+        buyer: String,
+        quantity: i32,
+    },
+    SetNote(usize, String),
+    AddTag(usize, String),
+    RemoveTag(usize, String),
+}
+
+impl DMHelper {
+    pub(super) fn show_cart_panel(&mut self, ui: &mut Ui) {
+        let mut action = None;
+        let rounding = self.settings.rounding;
+        let active_index = self.carts.active_index();
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("active_cart")
+                .selected_text(self.carts.active().name.as_str())
+                .show_ui(ui, |ui| {
+                    for (index, cart) in self.carts.carts().iter().enumerate() {
+                        if ui
+                            .selectable_label(index == active_index, cart.name.as_str())
+                            .clicked()
+                        {
+                            action = Some(CartAction::SwitchCart(index));
+                        }
+                    }
+                });
+            let history = self.carts.active().history();
+            let undo = history.next_undo().map(|op| op.label());
+            let redo = history.next_redo().map(|op| op.label());
+            if ui
+                .add_enabled(undo.is_some(), egui::Button::new("↶"))
+                .on_hover_text(format!("Cofnij {} (Ctrl+Z)", undo.unwrap_or_default()))
+                .clicked()
+            {
+                action = Some(CartAction::Undo);
+            }
+            if ui
+                .add_enabled(redo.is_some(), egui::Button::new("↷"))
+                .on_hover_text(format!(
+                    "Przywróć {} (Ctrl+Shift+Z)",
+                    redo.unwrap_or_default()
+                ))
+                .clicked()
+            {
+                action = Some(CartAction::Redo);
+            }
+            let menu = ui.menu_button("⚙", |ui| {
+                ui.label("Nazwa:");
+                let name = self
+                    .cart_name_draft
+                    .get_or_insert_with(|| self.carts.active().name.clone());
+                if ui.text_edit_singleline(name).lost_focus() {
+                    action = Some(CartAction::RenameCart(name.clone()));
+                }
+                ui.separator();
+                let current_budget = self.carts.active().budget;
+                let mut budget = current_budget;
+                let mut limited = budget.is_some();
+                ui.checkbox(&mut limited, "Budżet");
+                if limited {
+                    let budget = budget.get_or_insert(Budget {
+                        limit: Money::ZERO,
+                        currency: Some(Currency::Eur),
+                    });
+                    ui.horizontal(|ui| {
+                        let mut limit = budget.limit.to_f64();
+                        if ui
+                            .add(egui::DragValue::new(&mut limit).speed(1.0).max_decimals(2))
+                            .changed()
+                        {
+                            budget.limit = Money::from_f64(limit).max(Money::ZERO);
+                        }
+                        egui::ComboBox::from_id_source("budget_currency")
+                            .selected_text(budget.currency_code())
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut budget.currency, None, "PLN");
+                                for currency in Currency::ALL {
+                                    ui.selectable_value(
+                                        &mut budget.currency,
+                                        Some(currency),
+                                        currency.code(),
+                                    );
+                                }
+                            });
+                    });
+                } else {
+                    budget = None;
+                }
+                if budget != current_budget {
+                    action = Some(CartAction::SetBudget(budget));
+                }
+                if self.carts.carts().len() > 1 && ui.button("Usuń ten koszyk").clicked() {
+                    action = Some(CartAction::RemoveCart);
+                    ui.close_menu();
+                }
+            });
+            // A name typed into a menu closed without Enter is kept as well.
+            if menu.inner.is_none() {
+                if let Some(name) = self.cart_name_draft.take() {
+                    if name != self.carts.active().name {
+                        action = Some(CartAction::RenameCart(name));
+                    }
+                }
+            }
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_cart_name)
+                    .hint_text("nowy koszyk")
+                    .desired_width(100.0),
+            );
+            if ui.button("➕").on_hover_text("Dodaj koszyk").clicked()
+                && !self.new_cart_name.trim().is_empty()
+            {
+                action = Some(CartAction::CreateCart);
+            }
+        });
+        ui.separator();
+
+        let cart = self.carts.active();
+        let rates = cart.rates(&self.exchange_rates);
+        let other_carts: Vec<(usize, String)> = self
+            .carts
+            .carts()
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != active_index)
+            .map(|(index, cart)| (index, cart.name.clone()))
+            .collect();
+        let buyers = cart.buyers();
+        let tags = cart.tags();
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.cart_filter)
+                    .hint_text("🔍 nazwa, EAN lub notatka")
+                    .desired_width(160.0),
+            );
+            if !tags.is_empty() {
+                egui::ComboBox::from_id_source("cart_tag_filter")
+                    .selected_text(match &self.cart_tag_filter {
+                        Some(tag) => format!("#{}", tag),
+                        None => "wszystkie tagi".to_string(),
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.cart_tag_filter, None, "wszystkie tagi");
+                        for tag in &tags {
+                            ui.selectable_value(
+                                &mut self.cart_tag_filter,
+                                Some(tag.clone()),
+                                format!("#{}", tag),
+                            );
+                        }
+                    });
+            }
+            if (!self.cart_filter.is_empty() || self.cart_tag_filter.is_some())
+                && ui
+                    .small_button("✖")
+                    .on_hover_text("Pokaż wszystko")
+                    .clicked()
+            {
+                self.cart_filter.clear();
+                self.cart_tag_filter = None;
+            }
+        });
+        let tag_filter = self.cart_tag_filter.as_deref();
+        let shown = cart
+            .items()
+            .iter()
+            .filter(|line| line.matches(&self.cart_filter, tag_filter))
+            .count();
+        if shown < cart.items().len() {
+            ui.weak(format!(
+                "Pokazano {} z {} pozycji",
+                shown,
+                cart.items().len()
+            ));
+        }
+        egui::ScrollArea::vertical()
+            .max_height(ui.available_height() - 215.0)
+            .max_width(ui.available_width())
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                for (index, line) in cart.items().iter().enumerate() {
+                    if !line.matches(&self.cart_filter, tag_filter) {
+                        continue;
+                    }
+                    let item = &line.product;
+                    ui.horizontal(|ui| {
+                        if let Some(brand) = &item.brand {
+                            ui.strong(brand.as_str());
+                        }
+                        ui.label(item.name.to_string());
+                        if let Some(size) = &item.size {
+                            ui.weak(size.as_str());
+                        }
+                        ui.label(item.country.code());
+                        for tag in &line.tags {
+                            ui.weak(format!("#{}", tag));
+                        }
+                    });
+                    match &mut self.note_draft {
+                        Some((editing, draft)) if *editing == index => {
+                            let mut cancel = false;
+                            ui.horizontal(|ui| {
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(draft)
+                                        .hint_text("notatka")
+                                        .desired_width(200.0),
+                                );
+                                let entered = response.lost_focus()
+                                    && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                                if ui.small_button("✔").clicked() || entered {
+                                    action = Some(CartAction::SetNote(index, draft.clone()));
+                                }
+                                cancel = ui.small_button("✖").clicked();
+                            });
+                            if cancel {
+                                self.note_draft = None;
+                            }
+                        }
+                        _ if !line.note.is_empty() => {
+                            ui.weak(format!("📝 {}", line.note));
+                        }
+                        _ => {}
+                    }
+                    if !line.shares.is_empty() {
+                        let mut parts: Vec<String> = line
+                            .shares
+                            .iter()
+                            .map(|share| format!("{} {}", share.buyer, share.quantity))
+                            .collect();
+                        if line.unassigned() > 0 {
+                            parts.push(format!("nieprzypisane {}", line.unassigned()));
+                        }
+                        ui.weak(parts.join(", "));
+                    }
+                    ui.horizontal(|ui| {
+                        let mut quantity = match self.quantity_draft {
+                            Some((line, quantity)) if line == index => quantity,
+                            _ => item.quantity,
+                        };
+                        // Unclamped so a saved quantity outside the range is
+                        // left alone; `Cart::set_quantity` rejects typed ones.
+                        let response = ui.add(
+                            egui::DragValue::new(&mut quantity)
+                                .speed(1.0)
+                                .range(1..=i32::MAX)
+                                .clamp_to_range(false),
+                        );
+                        if response.changed() {
+                            self.quantity_draft = Some((index, quantity));
+                        }
+                        // One change per drag or edit, not one per frame.
+                        if response.drag_stopped() || response.lost_focus() {
+                            if let Some((_, quantity)) =
+                                self.quantity_draft.take_if(|(line, _)| *line == index)
+                            {
+                                if quantity != item.quantity {
+                                    action = Some(CartAction::SetQuantity(index, quantity));
+                                }
+                            }
+                        }
+                        ui.label(format!("x {} {}", item.price, item.currency()));
+                        ui.label(format!("= {} {}", line.total(), item.currency()));
+                        ui.label(format!(
+                            "/ {} PLN",
+                            pricing::to_pln(line.total(), line.exchange_rate, rounding.mode)
+                        ))
+                        .on_hover_text(format!("po kursie {}", line.exchange_rate));
+                        ui.menu_button("👥", |ui| {
+                            ui.label("Dla kogo:");
+                            for buyer in &buyers {
+                                let current = line.share_of(buyer);
+                                let mut quantity = current;
+                                let changed = ui
+                                    .horizontal(|ui| {
+                                        ui.label(buyer.as_str());
+                                        // Left unclamped so a share above the
+                                        // range is not rewritten without input;
+                                        // `Cart::assign` caps typed values.
+                                        ui.add(
+                                            egui::DragValue::new(&mut quantity)
+                                                .speed(1.0)
+                                                .range(0..=(current + line.unassigned()).max(0))
+                                                .clamp_to_range(false),
+                                        )
+                                        .changed()
+                                    })
+                                    .inner;
+                                if changed && quantity != current {
+                                    action = Some(CartAction::Assign {
+                                        line: index,
+                                        buyer: buyer.clone(),
+                                        quantity,
+                                    });
+                                }
+                            }
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.new_buyer_name)
+                                        .hint_text("nowa osoba")
+                                        .desired_width(100.0),
+                                );
+                                if ui
+                                    .button("➕")
+                                    .on_hover_text("Przypisz resztę tej pozycji")
+                                    .clicked()
+                                    && !self.new_buyer_name.trim().is_empty()
+                                {
+                                    action = Some(CartAction::Assign {
+                                        line: index,
+                                        buyer: self.new_buyer_name.trim().to_string(),
+                                        quantity: line.unassigned(),
+                                    });
+                                    self.new_buyer_name.clear();
+                                }
+                            });
+                        });
+                        if ui.small_button("📝").on_hover_text("Notatka").clicked() {
+                            self.note_draft = Some((index, line.note.clone()));
+                        }
+                        ui.menu_button("🏷", |ui| {
+                            for tag in &line.tags {
+                                if ui
+                                    .button(format!("✖ #{}", tag))
+                                    .on_hover_text("Usuń tag")
+                                    .clicked()
+                                {
+                                    action = Some(CartAction::RemoveTag(index, tag.clone()));
+                                }
+                            }
+                            for tag in tags.iter().filter(|tag| !line.tags.contains(tag)) {
+                                if ui.button(format!("➕ #{}", tag)).clicked() {
+                                    action = Some(CartAction::AddTag(index, tag.clone()));
+                                }
+                            }
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.new_tag)
+                                        .hint_text("nowy tag")
+                                        .desired_width(100.0),
+                                );
+                                if ui.button("➕").on_hover_text("Dodaj tag").clicked()
+                                    && !self.new_tag.trim().is_empty()
+                                {
+                                    action = Some(CartAction::AddTag(
+                                        index,
+                                        self.new_tag.trim().trim_start_matches('#').to_string(),
+                                    ));
+                                    self.new_tag.clear();
+                                }
+                            });
+                        });
+                        if !other_carts.is_empty() {
+                            ui.menu_button("⇄", |ui| {
+                                for (to, name) in &other_carts {
+                                    if ui.button(format!("Przenieś do: {}", name)).clicked() {
+                                        action = Some(CartAction::MoveLine {
+                                            line: index,
+                                            to: *to,
+                                        });
+                                        ui.close_menu();
+                                    }
+                                    if ui.button(format!("Kopiuj do: {}", name)).clicked() {
+                                        action = Some(CartAction::CopyLine {
+                                            line: index,
+                                            to: *to,
+                                        });
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+                        }
+                        if ui
+                            .small_button("🗑")
+                            .on_hover_text("Usuń z koszyka")
+                            .clicked()
+                        {
+                            action = Some(CartAction::Remove(index));
+                        }
+                    });
+                    ui.separator();
+                }
+            });
+
+        ui.horizontal(|ui| {
+            let mut pinned = cart.exchange_rates.is_some();
+            if ui
+                .checkbox(&mut pinned, "Własny kurs")
+                .on_hover_text("Kurs nowych pozycji tego koszyka, niezależny od kursów NBP")
+                .changed()
+            {
+                action = Some(CartAction::PinRates(pinned));
+            }
+            if pinned {
+                let mut currencies: Vec<Currency> = cart
+                    .totals()
+                    .iter()
+                    .map(|(currency, _)| *currency)
+                    .collect();
+                if currencies.is_empty() {
+                    currencies.push(Currency::Eur);
+                }
+                for currency in currencies {
+                    ui.label(currency.code());
+                    let mut rate = rates.get(currency).to_f64();
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut rate)
+                                .speed(0.0001)
+                                .max_decimals(6),
+                        )
+                        .changed()
+                    {
+                        action = Some(CartAction::SetRate(currency, Rate::from_f64(rate)));
+                    }
+                }
+            } else {
+                ui.label(format!("Kurs euro: {}", rates.get(Currency::Eur)));
+            }
+        });
+        let totals: Vec<String> = cart
+            .totals()
+            .iter()
+            .map(|(currency, total)| format!("{} {}", total, currency))
+            .collect();
+        ui.label(format!(
+            "Suma: {}, suma: {}PLN",
+            totals.join(" + "),
+            cart.total_pln(rounding)
+        ));
+        if let Some(status) = cart.budget_status(rates, rounding, None) {
+            let mut bar = egui::ProgressBar::new(status.fraction().min(1.0))
+                .text(format!("Budżet: {}", super::budget_usage(&status)));
+            if status.is_over() {
+                bar = bar.fill(ui.visuals().error_fg_color);
+            }
+            ui.add(bar);
+            if status.is_over() {
+                ui.colored_label(ui.visuals().error_fg_color, super::budget_warning(&status));
+            }
+        }
+        if !buyers.is_empty() {
+            egui::CollapsingHeader::new("Podział na osoby").show(ui, |ui| {
+                for buyer_total in cart.buyer_totals(rounding) {
+                    let totals: Vec<String> = buyer_total
+                        .totals
+                        .iter()
+                        .map(|(currency, total)| format!("{} {}", total, currency))
+                        .collect();
+                    ui.label(format!(
+                        "{}: {} / {} PLN",
+                        export::buyer_label(buyer_total.buyer.as_deref()),
+                        totals.join(" + "),
+                        buyer_total.total_pln
+                    ));
+                }
+                if ui.button("Eksport podziału CSV").clicked() {
+                    action = Some(CartAction::ExportBuyers);
+                }
+            });
+        }
+        egui::CollapsingHeader::new("Odsprzedaż").show(ui, |ui| {
+            let mut rules = self.settings.resale;
+            ui.horizontal(|ui| {
+                ui.label("Marża %:");
+                let mut margin = rules.margin_basis_points as f64 / 100.0;
+                if ui
+                    .add(egui::DragValue::new(&mut margin).speed(0.1).max_decimals(2))
+                    .changed()
+                {
+                    rules.margin_basis_points = (margin * 100.0).round() as i64;
+                }
+                ui.label("Opłata za sztukę:");
+                let mut fee = rules.fee_per_item.to_f64();
+                if ui
+                    .add(egui::DragValue::new(&mut fee).speed(0.1).max_decimals(2))
+                    .changed()
+                {
+                    rules.fee_per_item = Money::from_f64(fee);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Koszt wyjazdu (PLN):");
+                let mut trip_cost = cart.trip_cost.to_f64();
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut trip_cost)
+                            .speed(1.0)
+                            .max_decimals(2),
+                    )
+                    .changed()
+                {
+                    action = Some(CartAction::SetTripCost(Money::from_f64(trip_cost)));
+                }
+                egui::ComboBox::from_id_source("trip_cost_split")
+                    .selected_text(rules.trip_cost_split.label())
+                    .show_ui(ui, |ui| {
+                        for split in TripCostSplit::ALL {
+                            ui.selectable_value(&mut rules.trip_cost_split, split, split.label());
+                        }
+                    });
+            });
+            if rules != self.settings.resale {
+                action = Some(CartAction::SetResaleRules(rules));
+            }
+
+            let quote = resale::quote(cart, rounding, &rules);
+            egui::Grid::new("resale_lines")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Produkt");
+                    ui.label("Koszt");
+                    ui.label("Cena");
+                    ui.label("Za sztukę");
+                    ui.end_row();
+                    for (line, priced) in cart.items().iter().zip(&quote.lines) {
+                        ui.label(line.product.name.as_str());
+                        ui.label(format!("{} PLN", priced.cost()));
+                        ui.label(format!("{} PLN", priced.price()));
+                        ui.label(format!("{} PLN", priced.unit_price(rounding)));
+                        ui.end_row();
+                    }
+                });
+            ui.label(format!(
+                "Koszt: {} PLN, sprzedaż: {} PLN, zysk: {} PLN",
+                quote.total_cost(),
+                quote.total_price(),
+                quote.profit()
+            ));
+        });
+        let cart_is_empty = cart.is_empty();
+        ui.horizontal(|ui| {
+            if ui
+                .button("Zacznij od nowa")
+                .on_hover_text("Archiwizuje obecny koszyk i zaczyna pusty")
+                .clicked()
+            {
+                self.start_new_cart();
+            }
+            if !cart_is_empty
+                && ui
+                    .button("Eksport CSV")
+                    .on_hover_text("Zapisuje koszyk jako CSV dla arkusza kalkulacyjnego")
+                    .clicked()
+            {
+                self.export_csv();
+            }
+            if !cart_is_empty {
+                ui.menu_button("Podsumowanie", |ui| {
+                    if ui.button("HTML").clicked() {
+                        self.export_summary(false);
+                        ui.close_menu();
+                    }
+                    if ui.button("HTML + PDF").clicked() {
+                        self.export_summary(true);
+                        ui.close_menu();
+                    }
+                });
+            }
+            if self.confirm_clear_cart {
+                ui.label("Usunąć wszystkie pozycje?");
+                if ui.button("Tak").clicked() {
+                    action = Some(CartAction::Clear);
+                    self.confirm_clear_cart = false;
+                }
+                if ui.button("Nie").clicked() {
+                    self.confirm_clear_cart = false;
+                }
+            } else if !cart_is_empty && ui.button("Wyczyść koszyk").clicked() {
+                self.confirm_clear_cart = true;
+            }
+        });
+
+        if action.is_some() {
+            self.note_draft = None;
+        }
+        let changed = match action {
+            Some(CartAction::SetQuantity(index, quantity)) => {
+                self.carts.active_mut().set_quantity(index, quantity)
+            }
+            Some(CartAction::Remove(index)) => self.carts.active_mut().remove_at(index).is_some(),
+            Some(CartAction::Undo) => {
+                self.undo_cart();
+                false
+            }
+            Some(CartAction::Redo) => {
+                self.redo_cart();
+                false
+            }
+            Some(CartAction::Clear) => {
+                self.carts.active_mut().clear();
+                true
+            }
+            Some(CartAction::MoveLine { line, to }) => self.carts.move_line(active_index, line, to),
+            Some(CartAction::CopyLine { line, to }) => self.carts.copy_line(active_index, line, to),
+            Some(CartAction::SwitchCart(index)) => {
+                self.carts.set_active(index);
+                self.confirm_clear_cart = false;
+                self.cart_tag_filter = None;
+                true
+            }
+            Some(CartAction::CreateCart) => {
+                self.carts.create(self.new_cart_name.trim());
+                self.new_cart_name.clear();
+                true
+            }
+            Some(CartAction::RemoveCart) => {
+                if let Some(removed) = self.carts.remove(active_index) {
+                    self.archive_removed_cart(removed);
+                }
+                true
+            }
+            Some(CartAction::PinRates(pinned)) => {
+                self.carts.active_mut().exchange_rates = if pinned {
+                    Some(self.exchange_rates.clone())
+                } else {
+                    None
+                };
+                true
+            }
+            Some(CartAction::Assign {
+                line,
+                buyer,
+                quantity,
+            }) => self.carts.active_mut().assign(line, &buyer, quantity),
+            Some(CartAction::SetNote(line, note)) => self.carts.active_mut().set_note(line, &note),
+            Some(CartAction::AddTag(line, tag)) => self.carts.active_mut().add_tag(line, &tag),
+            Some(CartAction::RemoveTag(line, tag)) => {
+                self.carts.active_mut().remove_tag(line, &tag)
+            }
+            Some(CartAction::SetResaleRules(rules)) => {
+                self.settings.resale = rules;
+                self.save_settings();
+                false
+            }
+            Some(CartAction::SetTripCost(trip_cost)) => {
+                self.carts.active_mut().trip_cost = trip_cost.max(Money::ZERO);
+                true
+            }
+            Some(CartAction::RenameCart(name)) => {
+                self.cart_name_draft = None;
+                match self.carts.rename(active_index, &name) {
+                    Ok(changed) => changed,
+                    Err(e) => {
+                        self.status.error("Zmiana nazwy koszyka", e);
+                        false
+                    }
+                }
+            }
+            Some(CartAction::SetBudget(budget)) => {
+                self.carts.active_mut().budget = budget;
+                true
+            }
+            Some(CartAction::ExportBuyers) => {
+                self.export_buyers_csv();
+                false
+            }
+            Some(CartAction::SetRate(currency, rate)) => {
+                if let Some(rates) = &mut self.carts.active_mut().exchange_rates {
+                    rates.set_override(currency, rate);
+                }
+                true
+            }
+            None => false,
+        };
+        if changed {
+            self.save_cart();
+        }
+    }
+}
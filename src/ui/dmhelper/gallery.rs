@@ -0,0 +1,165 @@
+use dmhelper_core::{lookup, ImageTask, Product};
+use egui::{vec2, Align2, FontId, Sense, TextureHandle, Ui, Vec2};
+
+use super::{image_to_color_image, DMHelper};
+
+const THUMBNAIL_SIZE: Vec2 = vec2(100.0, 200.0);
+const LIGHTBOX_SIZE: Vec2 = vec2(600.0, 600.0);
+
+/// One picture of the product in the product panel.
+enum GalleryImage {
+    NotLoaded,
+    Loading(ImageTask),
+    Loaded(TextureHandle),
+    Failed,
+}
+
+/// Pictures of the product in the product panel; the main one comes with
+/// the lookup, the others are fetched when they are first shown.
+#[derive(Default)]
+pub(super) struct Gallery {
+    images: Vec<GalleryImage>,
+    index: usize,
+    lightbox: bool,
+}
+
+fn load_texture(ctx: &egui::Context, bytes: &[u8], index: usize) -> Option<TextureHandle> {
+    let image = lookup::decode_image(bytes).ok()?;
+    Some(ctx.load_texture(
+        format!("product_image_{}", index),
+        image_to_color_image(image),
+        egui::TextureOptions::default(),
+    ))
+}
+
+/// Grey box shown instead of a picture that is missing or failed to load.
+fn placeholder(ui: &mut Ui, size: Vec2) {
+    let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+    ui.painter()
+        .rect_filled(rect, 4.0, ui.visuals().faint_bg_color);
+    ui.painter().text(
+        rect.center(),
+        Align2::CENTER_CENTER,
+        "📷\nbrak zdjęcia",
+        FontId::proportional(14.0),
+        ui.visuals().weak_text_color(),
+    );
+}
+
+impl Gallery {
+    /// A gallery for `product` with its main image, if it has one, ready.
+    pub(super) fn new(ctx: &egui::Context, product: &Product) -> Self {
+        // Fixture products may have a main image without any URL.
+        let count = product
+            .image_urls
+            .len()
+            .max(usize::from(product.image.is_some()));
+        let mut images: Vec<GalleryImage> = (0..count).map(|_| GalleryImage::NotLoaded).collect();
+        if let Some(first) = images.first_mut() {
+            *first = match product
+                .image
+                .as_deref()
+                .and_then(|bytes| load_texture(ctx, bytes, 0))
+            {
+                Some(texture) => GalleryImage::Loaded(texture),
+                None => GalleryImage::Failed,
+            };
+        }
+        Self {
+            images,
+            index: 0,
+            lightbox: false,
+        }
+    }
+
+    /// Draws the current picture, `size` at most, or a placeholder.
+    fn show_image(&self, ui: &mut Ui, size: Vec2) -> Option<egui::Response> {
+        match self.images.get(self.index) {
+            Some(GalleryImage::Loaded(texture)) => Some(
+                ui.add(
+                    egui::Image::from_texture(texture)
+                        .max_size(size)
+                        .sense(Sense::click()),
+                ),
+            ),
+            Some(GalleryImage::Loading(_)) | Some(GalleryImage::NotLoaded) => {
+                ui.spinner();
+                None
+            }
+            Some(GalleryImage::Failed) | None => {
+                placeholder(ui, vec2(size.x, size.x));
+                None
+            }
+        }
+    }
+
+    /// Previous/next buttons, shown only for more than one picture.
+    fn show_navigation(&mut self, ui: &mut Ui) {
+        let count = self.images.len();
+        if count < 2 {
+            return;
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(self.index > 0, egui::Button::new("◀"))
+                .clicked()
+            {
+                self.index -= 1;
+            }
+            ui.label(format!("{} / {}", self.index + 1, count));
+            if ui
+                .add_enabled(self.index + 1 < count, egui::Button::new("▶"))
+                .clicked()
+            {
+                self.index += 1;
+            }
+        });
+    }
+
+    pub(super) fn show(&mut self, ui: &mut Ui) {
+        if let Some(response) = self.show_image(ui, THUMBNAIL_SIZE) {
+            if response.on_hover_text("Powiększ").clicked() {
+                self.lightbox = true;
+            }
+        }
+        self.show_navigation(ui);
+
+        let mut open = self.lightbox;
+        egui::Window::new("Zdjęcie produktu")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ui.ctx(), |ui| {
+                self.show_image(ui, LIGHTBOX_SIZE);
+                self.show_navigation(ui);
+            });
+        self.lightbox = open;
+    }
+}
+
+impl DMHelper {
+    /// Takes fetched pictures and starts fetching the one being shown.
+    pub(super) fn poll_gallery(&mut self, ctx: &egui::Context) {
+        for (index, image) in self.gallery.images.iter_mut().enumerate() {
+            let GalleryImage::Loading(task) = image else {
+                continue;
+            };
+            let Some(result) = task.poll() else {
+                continue;
+            };
+            *image = match result
+                .ok()
+                .and_then(|bytes| load_texture(ctx, &bytes, index))
+            {
+                Some(texture) => GalleryImage::Loaded(texture),
+                None => GalleryImage::Failed,
+            };
+        }
+        let Some(product) = &self.product else {
+            return;
+        };
+        let index = self.gallery.index;
+        if let Some(image @ GalleryImage::NotLoaded) = self.gallery.images.get_mut(index) {
+            *image = GalleryImage::Loading(ImageTask::spawn(self.source.clone(), product, index));
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use dmhelper_core::{import, pricing, Country, ImportEntry, ImportReport, LookupTask};
+use egui::Ui;
+use std::{collections::VecDeque, fs};
+
+use super::DMHelper;
+
+/// An EAN list being resolved one lookup at a time, so the window keeps
+/// responding.
+pub(super) struct ImportJob {
+    country: Country,
+    pending: VecDeque<ImportEntry>,
+    current: Option<(ImportEntry, LookupTask)>,
+    report: ImportReport,
+    total: usize,
+}
+
+impl ImportJob {
+    fn done(&self) -> usize {
+        self.total - self.pending.len() - usize::from(self.current.is_some())
+    }
+}
+
+impl DMHelper {
+    fn start_import(&mut self) {
+        let list = import::parse_ean_list(&self.import_text);
+        self.import_report = None;
+        self.import_job = Some(ImportJob {
+            country: self.country,
+            total: list.entries.len(),
+            report: ImportReport::new(&list),
+            pending: list.entries.into(),
+            current: None,
+        });
+    }
+
+    /// Advances the running import: takes finished lookups, serves cached
+    /// products right away and starts the next lookup.
+    pub(super) fn poll_import(&mut self, ctx: &egui::Context) {
+        let Some(job) = &mut self.import_job else {
+            return;
+        };
+        let mut fetched = Vec::new();
+        loop {
+            if let Some((entry, task)) = &job.current {
+                let Some(result) = task.poll() else {
+                    break;
+                };
+                if let Ok(product) = &result {
+                    self.cached_items
+                        .insert(job.country, &entry.ean, product.clone());
+                    fetched.push(product.clone());
+                    self.cache_changed = true;
+                }
+                job.report.record(entry, result);
+                job.current = None;
+            }
+            let Some(entry) = job.pending.pop_front() else {
+                self.import_report = self.import_job.take().map(|job| job.report);
+                break;
+            };
+            match self.cached_items.get(job.country, &entry.ean) {
+                Some(product) => job.report.record(&entry, Ok(product)),
+                None => {
+                    let task = LookupTask::spawn(self.source.clone(), job.country, &entry.ean);
+                    job.current = Some((entry, task));
+                }
+            }
+        }
+        for product in &fetched {
+            self.record_price(product);
+            self.check_watched(ctx, product);
+        }
+    }
+
+    fn cancel_import(&mut self) {
+        if let Some(job) = self.import_job.take() {
+            if let Some((_, task)) = job.current {
+                task.cancel();
+            }
+            self.import_report = Some(job.report);
+        }
+    }
+
+    fn add_imported_to_cart(&mut self) {
+        let Some(report) = self.import_report.take() else {
+            return;
+        };
+        let added = report.add_to_cart(self.carts.active_mut(), &self.exchange_rates);
+        self.status
+            .info(format!("Dodano do koszyka {} pozycji z listy", added));
+        self.save_cart();
+    }
+
+    /// Appends the contents of files dropped on the window to the list.
+    fn take_dropped_files(&mut self, ui: &Ui) {
+        let dropped = ui.ctx().input(|input| input.raw.dropped_files.clone());
+        for file in dropped {
+            let contents = match (&file.bytes, &file.path) {
+                (Some(bytes), _) => Ok(String::from_utf8_lossy(bytes).into_owned()),
+                (None, Some(path)) => fs::read_to_string(path),
+                (None, None) => continue,
+            };
+            match contents {
+                Ok(contents) => {
+                    if !self.import_text.is_empty() && !self.import_text.ends_with('\n') {
+                        self.import_text.push('\n');
+                    }
+                    self.import_text.push_str(&contents);
+                }
+                Err(e) => self.status.error("Wczytanie listy", e),
+            }
+        }
+    }
+
+    pub(super) fn show_import_panel(&mut self, ui: &mut Ui) {
+        self.take_dropped_files(ui);
+        ui.label("EAN[,ilość] w każdej linii; można też upuścić plik.");
+        egui::ScrollArea::vertical()
+            .id_source("import_text")
+            .max_height(120.0)
+            .show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.import_text)
+                        .hint_text("4058172936760,2")
+                        .desired_rows(5),
+                );
+            });
+
+        if let Some(job) = &self.import_job {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(format!("Sprawdzono {} z {}", job.done(), job.total));
+            });
+            if ui.button("Anuluj").clicked() {
+                self.cancel_import();
+            }
+            return;
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Sprawdź listę").clicked() {
+                self.start_import();
+            }
+            if !self.import_text.is_empty() && ui.button("Wyczyść").clicked() {
+                self.import_text.clear();
+            }
+        });
+
+        let Some(report) = &self.import_report else {
+            return;
+        };
+        ui.label(format!("Znalezione ({}):", report.found.len()));
+        for product in &report.found {
+            ui.label(format!(
+                "  {} x {} ({})",
+                product.quantity, product.name, product.ean
+            ));
+        }
+        if !report.not_found.is_empty() {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("Nie znaleziono: {}", report.not_found.join(", ")),
+            );
+        }
+        for (ean, error) in &report.failed {
+            ui.colored_label(ui.visuals().error_fg_color, format!("{}: {}", ean, error));
+        }
+        for duplicate in &report.duplicates {
+            ui.label(format!(
+                "Połączono {} linii z EAN {}",
+                duplicate.occurrences, duplicate.ean
+            ));
+        }
+        for skipped in &report.skipped {
+            ui.weak(format!(
+                "Pominięto linię {}: {}",
+                skipped.line_number, skipped.text
+            ));
+        }
+        let found = report.found.len();
+        let cart = self.carts.active();
+        let budget_after = cart.budget_status(
+            cart.rates(&self.exchange_rates),
+            self.settings.rounding,
+            report.found.iter().map(|product| {
+                (
+                    pricing::line_total(product.price, product.quantity),
+                    product.currency(),
+                )
+            }),
+        );
+        let over_budget = budget_after.is_some_and(|status| status.is_over());
+        if let Some(status) = budget_after.filter(|_| found > 0) {
+            ui.label(format!("Po dodaniu: {}", super::budget_usage(&status)));
+            if over_budget {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("⚠ {}", super::budget_warning(&status)),
+                );
+            }
+        }
+        ui.horizontal(|ui| {
+            let add_label = if over_budget {
+                format!("Dodaj {} mimo przekroczenia budżetu", found)
+            } else {
+                format!("Dodaj {} do koszyka", found)
+            };
+            if found > 0 && ui.button(add_label).clicked() {
+                self.add_imported_to_cart();
+            }
+            if ui.button("Odrzuć").clicked() {
+                self.import_report = None;
+            }
+        });
+    }
+}
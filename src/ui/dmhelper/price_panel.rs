@@ -0,0 +1,236 @@
+use chrono::{DateTime, Local, Utc};
+use dmhelper_core::{Money, PriceSeries};
+use egui::{pos2, vec2, Align2, Color32, FontId, Pos2, Rect, Sense, Shape, Stroke, Ui};
+
+use super::DMHelper;
+
+const CHART_SIZE: egui::Vec2 = vec2(520.0, 160.0);
+const MIN_COLOR: Color32 = Color32::from_rgb(60, 170, 90);
+const MAX_COLOR: Color32 = Color32::from_rgb(210, 80, 70);
+
+fn short_date(at: DateTime<Utc>) -> String {
+    at.with_timezone(&Local).format("%d.%m.%Y").to_string()
+}
+
+/// Draws `points` as a step line over time, marking the lowest and highest
+/// price and the time of the last change.
+fn price_chart(
+    ui: &mut Ui,
+    points: &[(DateTime<Utc>, Money)],
+    unit: &str,
+    last_change: Option<DateTime<Utc>>,
+) {
+    let (response, painter) = ui.allocate_painter(CHART_SIZE, Sense::hover());
+    let visuals = ui.visuals();
+    painter.rect_filled(response.rect, 4.0, visuals.extreme_bg_color);
+    let Some(&(first_at, _)) = points.first() else {
+        return;
+    };
+    let rect = response.rect.shrink2(vec2(50.0, 18.0));
+    let end_at = Utc::now().max(points[points.len() - 1].0);
+    let span = (end_at - first_at).num_seconds().max(1) as f32;
+    let low = points.iter().map(|(_, price)| *price).min().unwrap();
+    let high = points.iter().map(|(_, price)| *price).max().unwrap();
+    let range = (high - low).to_f64().max(0.01) as f32;
+    let x = |at: DateTime<Utc>| {
+        rect.left() + rect.width() * (at - first_at).num_seconds() as f32 / span
+    };
+    let y = |price: Money| rect.bottom() - rect.height() * (price - low).to_f64() as f32 / range;
+
+    let mut line: Vec<Pos2> = Vec::new();
+    for (index, &(at, price)) in points.iter().enumerate() {
+        if index > 0 {
+            line.push(pos2(x(at), y(points[index - 1].1)));
+        }
+        line.push(pos2(x(at), y(price)));
+    }
+    line.push(pos2(x(end_at), y(points[points.len() - 1].1)));
+    painter.add(Shape::line(
+        line,
+        Stroke::new(2.0, visuals.strong_text_color()),
+    ));
+
+    let font = FontId::proportional(11.0);
+    let text_color = visuals.text_color();
+    if let Some(at) = last_change {
+        let change_x = x(at);
+        painter.add(Shape::dashed_line(
+            &[pos2(change_x, rect.top()), pos2(change_x, rect.bottom())],
+            Stroke::new(1.0, visuals.weak_text_color()),
+            4.0,
+            3.0,
+        ));
+        painter.text(
+            pos2(change_x, response.rect.top() + 2.0),
+            Align2::CENTER_TOP,
+            format!("zmiana {}", short_date(at)),
+            font.clone(),
+            text_color,
+        );
+    }
+    for (price, color, label) in [(low, MIN_COLOR, "min"), (high, MAX_COLOR, "max")] {
+        let Some(&(at, _)) = points.iter().find(|(_, p)| *p == price) else {
+            continue;
+        };
+        let point = pos2(x(at), y(price));
+        painter.circle_filled(point, 4.0, color);
+        painter.text(
+            point + vec2(6.0, 0.0),
+            Align2::LEFT_BOTTOM,
+            format!("{} {}", label, price),
+            font.clone(),
+            color,
+        );
+    }
+    painter.text(
+        pos2(response.rect.left() + 4.0, rect.bottom()),
+        Align2::LEFT_CENTER,
+        low.to_string(),
+        font.clone(),
+        text_color,
+    );
+    painter.text(
+        pos2(response.rect.left() + 4.0, rect.top()),
+        Align2::LEFT_CENTER,
+        high.to_string(),
+        font.clone(),
+        text_color,
+    );
+    painter.text(
+        pos2(response.rect.right() - 4.0, rect.center().y),
+        Align2::RIGHT_CENTER,
+        unit,
+        font.clone(),
+        text_color,
+    );
+    let axis = Rect::from_min_max(
+        pos2(rect.left(), response.rect.bottom() - 14.0),
+        pos2(rect.right(), response.rect.bottom()),
+    );
+    painter.text(
+        axis.left_center(),
+        Align2::LEFT_CENTER,
+        short_date(first_at),
+        font.clone(),
+        text_color,
+    );
+    painter.text(
+        axis.right_center(),
+        Align2::RIGHT_CENTER,
+        short_date(end_at),
+        font,
+        text_color,
+    );
+}
+
+impl DMHelper {
+    /// Opens the price history window on the product in the product panel,
+    /// or on the first product with a history.
+    pub(super) fn open_price_chart(&mut self) {
+        let current = self
+            .product
+            .as_ref()
+            .filter(|product| {
+                self.price_history
+                    .get(product.country, &product.ean)
+                    .is_some()
+            })
+            .map(|product| (product.country, product.ean.clone()));
+        self.price_chart = current.or_else(|| {
+            self.price_history
+                .series()
+                .first()
+                .map(|series| (series.country, series.ean.clone()))
+        });
+        if self.price_chart.is_none() {
+            self.status
+                .info("Historia cen jest pusta; ceny zapisują się przy wyszukiwaniu produktów");
+        }
+    }
+
+    pub(super) fn show_price_window(&mut self, ctx: &egui::Context) {
+        let Some((country, ean)) = self.price_chart.clone() else {
+            return;
+        };
+        let mode = self.settings.rounding.mode;
+        let mut open = true;
+        let mut selected = None;
+        egui::Window::new("Historia cen")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let Some(series) = self.price_history.get(country, &ean) else {
+                    return;
+                };
+                egui::ComboBox::from_id_source("price_series")
+                    .selected_text(series_label(series))
+                    .width(CHART_SIZE.x)
+                    .show_ui(ui, |ui| {
+                        for other in self.price_history.series() {
+                            let is_selected = other.country == country && other.ean == ean;
+                            if ui
+                                .selectable_label(is_selected, series_label(other))
+                                .clicked()
+                            {
+                                selected = Some((other.country, other.ean.clone()));
+                            }
+                        }
+                    });
+                let currency = series.currency();
+                let last_change = series.last_change().map(|(_, after)| after.at);
+                let points: Vec<(DateTime<Utc>, Money)> = series
+                    .observations
+                    .iter()
+                    .map(|observation| (observation.at, observation.price))
+                    .collect();
+                ui.label(format!("Cena w {}", currency));
+                price_chart(ui, &points, currency.code(), last_change);
+                let points: Vec<(DateTime<Utc>, Money)> = series
+                    .observations
+                    .iter()
+                    .map(|observation| (observation.at, observation.pln(mode)))
+                    .collect();
+                ui.label("Cena w PLN (po kursie z dnia sprawdzenia)");
+                price_chart(ui, &points, "PLN", last_change);
+
+                if let (Some(min), Some(max)) = (series.min(), series.max()) {
+                    ui.label(format!(
+                        "Najniższa: {} {} ({}), najwyższa: {} {} ({})",
+                        min.price,
+                        currency,
+                        short_date(min.at),
+                        max.price,
+                        currency,
+                        short_date(max.at)
+                    ));
+                }
+                match series.last_change() {
+                    Some((before, after)) => ui.label(format!(
+                        "Ostatnia zmiana {}: {} → {} {}",
+                        short_date(after.at),
+                        before.price,
+                        after.price,
+                        currency
+                    )),
+                    None => ui.weak("Cena się nie zmieniła"),
+                };
+                ui.weak(format!(
+                    "{} odczytów od {}",
+                    series.observations.len(),
+                    series
+                        .observations
+                        .first()
+                        .map_or_else(String::new, |first| short_date(first.at))
+                ));
+            });
+        self.price_chart = if open {
+            selected.or(Some((country, ean)))
+        } else {
+            None
+        };
+    }
+}
+
+fn series_label(series: &PriceSeries) -> String {
+    format!("{} ({}, {})", series.name, series.ean, series.country)
+}
@@ -0,0 +1,228 @@
+use dmhelper_core::{lookup, pricing, search, Country, SearchHit, SearchTask, SearchUpdate};
+use egui::{vec2, Align2, FontId, Key, Sense, TextureHandle, Ui, Vec2};
+
+use super::{budget_warning, gallery::Gallery, image_to_color_image, DMHelper};
+
+const THUMBNAIL_SIZE: Vec2 = vec2(48.0, 48.0);
+
+/// A search hit with its thumbnail uploaded to the GPU.
+pub(super) struct SearchRow {
+    hit: SearchHit,
+    thumbnail: Option<TextureHandle>,
+}
+
+impl SearchRow {
+    fn new(hit: SearchHit) -> Self {
+        Self {
+            hit,
+            thumbnail: None,
+        }
+    }
+
+    /// Uploads the thumbnail of the row at `index` once it has arrived.
+    fn set_thumbnail(&mut self, ctx: &egui::Context, bytes: &[u8], index: usize) {
+        self.thumbnail = lookup::decode_image(bytes).ok().map(|image| {
+            ctx.load_texture(
+                format!("search_thumbnail_{}", index),
+                image_to_color_image(image),
+                egui::TextureOptions::default(),
+            )
+        });
+    }
+
+    fn show_thumbnail(&self, ui: &mut Ui) {
+        match &self.thumbnail {
+            Some(texture) => {
+                ui.add(
+                    egui::Image::from_texture(texture)
+                        .max_size(THUMBNAIL_SIZE)
+                        .maintain_aspect_ratio(true),
+                );
+            }
+            None => {
+                let (rect, _) = ui.allocate_exact_size(THUMBNAIL_SIZE, Sense::hover());
+                ui.painter()
+                    .rect_filled(rect, 4.0, ui.visuals().faint_bg_color);
+                ui.painter().text(
+                    rect.center(),
+                    Align2::CENTER_CENTER,
+                    "📷",
+                    FontId::proportional(16.0),
+                    ui.visuals().weak_text_color(),
+                );
+            }
+        }
+    }
+}
+
+impl DMHelper {
+    fn start_search(&mut self) {
+        let query = self.search_query.trim().to_string();
+        if query.is_empty() {
+            return;
+        }
+        if let Some(task) = self.search_task.take() {
+            task.cancel();
+        }
+        self.search_task = Some(SearchTask::spawn(self.source.clone(), self.country, &query));
+    }
+
+    pub(super) fn poll_search(&mut self, ctx: &egui::Context) {
+        let Some(update) = self.search_task.as_mut().and_then(SearchTask::poll) else {
+            return;
+        };
+        match update {
+            SearchUpdate::Hits(Ok(hits)) => {
+                if hits.is_empty() {
+                    let query = self.search_task.take().unwrap().query().to_string();
+                    self.status
+                        .info(format!("Nie znaleziono produktów dla „{}”", query));
+                }
+                self.search_results = hits.into_iter().map(SearchRow::new).collect();
+            }
+            SearchUpdate::Hits(Err(e)) => {
+                self.search_task = None;
+                self.status.error("Wyszukiwanie po nazwie", e);
+            }
+            SearchUpdate::Thumbnail(index, bytes) => {
+                if let Some(row) = self.search_results.get_mut(index) {
+                    row.set_thumbnail(ctx, &bytes, index);
+                }
+            }
+            SearchUpdate::Finished => self.search_task = None,
+        }
+    }
+
+    /// Looks up a search hit to show it in the product panel, adding it to
+    /// the active cart once it arrives if `add` is set.
+    fn open_search_hit(&mut self, ctx: &egui::Context, country: Country, ean: String, add: bool) {
+        self.country = country;
+        self.ean = ean;
+        self.pending_add = add.then(|| (country, self.ean.clone()));
+        self.start_lookup(ctx);
+    }
+
+    /// Adds the product in the product panel to the active cart if it was
+    /// looked up from the ➕ button of a search hit.
+    ///
+    /// A product that would exceed the cart's budget is left in the product
+    /// panel, where adding it anyway has to be confirmed.
+    pub(super) fn add_pending_to_cart(&mut self) {
+        let Some(product) = &mut self.product else {
+            return;
+        };
+        if self.pending_add.as_ref() != Some(&(product.country, product.ean.clone())) {
+            return;
+        }
+        self.pending_add = None;
+        product.quantity = product.quantity.max(1);
+        let cart = self.carts.active();
+        let budget_after = cart.budget_status(
+            cart.rates(&self.exchange_rates),
+            self.settings.rounding,
+            Some((
+                pricing::line_total(product.price, product.quantity),
+                product.currency(),
+            )),
+        );
+        if let Some(status) = budget_after.filter(|status| status.is_over()) {
+            self.status.error(
+                "Nie dodano do koszyka",
+                format!(
+                    "{}; potwierdź dodanie {} w panelu produktu",
+                    budget_warning(&status),
+                    product.name
+                ),
+            );
+            return;
+        }
+        let product = self.product.take().unwrap();
+        let exchange_rate = cart.rates(&self.exchange_rates).get(product.currency());
+        let name = product.name.clone();
+        if self.carts.active_mut().add(product, exchange_rate) {
+            self.status.info(format!(
+                "Dodano {} do koszyka {} (Ctrl+Z cofa)",
+                name,
+                self.carts.active().name
+            ));
+            self.gallery = Gallery::default();
+            self.save_cart();
+        }
+    }
+
+    pub(super) fn show_search_panel(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.search_query).hint_text("np. balea shampoo"),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+            if self
+                .search_task
+                .as_ref()
+                .is_some_and(|task| !task.hits_delivered())
+            {
+                ui.spinner();
+            } else if ui.button("🔍 Szukaj").clicked() || submitted {
+                self.start_search();
+            }
+        });
+        ui.weak(format!(
+            "w sklepie {}, do {} wyników",
+            self.country.code(),
+            search::MAX_RESULTS
+        ));
+        if self.search_results.is_empty() {
+            return;
+        }
+
+        let mut open = None;
+        egui::ScrollArea::vertical()
+            .id_source("search_results")
+            .max_height(300.0)
+            .show(ui, |ui| {
+                for row in &self.search_results {
+                    let hit = &row.hit;
+                    ui.horizontal(|ui| {
+                        row.show_thumbnail(ui);
+                        ui.vertical(|ui| {
+                            if let Some(brand) = &hit.brand {
+                                ui.strong(brand);
+                            }
+                            ui.label(hit.name.as_str());
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{} {}", hit.price, hit.country.currency()));
+                                ui.weak(format!("{} ({})", hit.ean, hit.country));
+                            });
+                            ui.horizontal(|ui| {
+                                if ui
+                                    .small_button("Pokaż")
+                                    .on_hover_text("Pokaż w panelu produktu")
+                                    .clicked()
+                                {
+                                    open = Some((hit.country, hit.ean.clone(), false));
+                                }
+                                if ui
+                                    .small_button("➕")
+                                    .on_hover_text("Dodaj 1 szt. do aktywnego koszyka")
+                                    .clicked()
+                                {
+                                    open = Some((hit.country, hit.ean.clone(), true));
+                                }
+                            });
+                        });
+                    });
+                    ui.separator();
+                }
+            });
+        if ui.small_button("Wyczyść wyniki").clicked() {
+            if let Some(task) = self.search_task.take() {
+                task.cancel();
+            }
+            self.search_results.clear();
+        }
+
+        if let Some((country, ean, add)) = open {
+            self.open_search_hit(ui.ctx(), country, ean, add);
+        }
+    }
+}
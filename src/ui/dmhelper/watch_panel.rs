@@ -0,0 +1,196 @@
+use chrono::Utc;
+use dmhelper_core::{watchlist, LookupTask, Money, Product};
+use egui::{Ui, UserAttentionType, ViewportCommand};
+
+use super::DMHelper;
+
+impl DMHelper {
+    fn save_watchlist(&mut self) {
+        if let Err(e) = self.watchlist.save(&watchlist::watchlist_path()) {
+            self.status.error("Zapis obserwowanych", e);
+        }
+    }
+
+    /// Stores the price of a looked up product on the watchlist and alerts
+    /// if it just dropped to or below the target.
+    pub(super) fn check_watched(&mut self, ctx: &egui::Context, product: &Product) {
+        if self
+            .watchlist
+            .position(product.country, &product.ean)
+            .is_none()
+        {
+            return;
+        }
+        if let Some(item) = self.watchlist.check(product, Utc::now()) {
+            self.status.alert(format!(
+                "{} kosztuje teraz {} {} (cel {} {})",
+                item.name,
+                product.price,
+                item.currency(),
+                item.target,
+                item.currency()
+            ));
+            ctx.send_viewport_cmd(ViewportCommand::RequestUserAttention(
+                UserAttentionType::Informational,
+            ));
+        }
+        self.save_watchlist();
+    }
+
+    /// Takes the result of the running watchlist check and starts the next
+    /// one that is due.
+    pub(super) fn poll_watchlist(&mut self, ctx: &egui::Context) {
+        if let Some(result) = self.watch_task.as_ref().and_then(|task| task.poll()) {
+            let task = self.watch_task.take().unwrap();
+            match result {
+                Ok(product) => {
+                    self.cached_items
+                        .insert(task.country(), task.ean(), product.clone());
+                    self.cache_changed = true;
+                    self.record_price(&product);
+                    self.check_watched(ctx, &product);
+                }
+                Err(e) => {
+                    self.watchlist
+                        .mark_checked(task.country(), task.ean(), Utc::now());
+                    self.save_watchlist();
+                    self.status.error("Sprawdzenie obserwowanego", e);
+                }
+            }
+        }
+        if self.watch_task.is_some() {
+            return;
+        }
+        if let Some(item) = self.watchlist.next_due(Utc::now()) {
+            self.watch_task = Some(LookupTask::spawn(
+                self.source.clone(),
+                item.country,
+                &item.ean,
+            ));
+        }
+    }
+
+    /// Target price field and button for the product in the product panel.
+    pub(super) fn show_watch_controls(&mut self, ui: &mut Ui) {
+        let Some(product) = &self.product else {
+            return;
+        };
+        let watched = self
+            .watchlist
+            .position(product.country, &product.ean)
+            .is_some();
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label(format!("Cel ({}):", product.currency()));
+            let mut target = self.watch_target.to_f64();
+            if ui
+                .add(
+                    egui::DragValue::new(&mut target)
+                        .speed(0.01)
+                        .max_decimals(2),
+                )
+                .changed()
+            {
+                self.watch_target = Money::from_f64(target).max(Money::ZERO);
+            }
+            let label = if watched {
+                "Zmień cel"
+            } else {
+                "👁 Obserwuj"
+            };
+            if ui
+                .button(label)
+                .on_hover_text("Powiadom, gdy cena spadnie do celu lub niżej")
+                .clicked()
+            {
+                self.watchlist.watch(product, self.watch_target, Utc::now());
+                self.status.info(format!(
+                    "Obserwowane: {} do {} {}",
+                    product.name,
+                    self.watch_target,
+                    product.currency()
+                ));
+                changed = true;
+            }
+        });
+        if changed {
+            self.save_watchlist();
+        }
+    }
+
+    pub(super) fn show_watch_panel(&mut self, ui: &mut Ui) {
+        if self.watchlist.is_empty() {
+            ui.weak("Nic nie jest obserwowane; dodaj produkt przyciskiem 👁 Obserwuj.");
+            return;
+        }
+        let mut removed = None;
+        let mut retarget = None;
+        let mut open = None;
+        for (index, item) in self.watchlist.items().iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{} ({})", item.name, item.country));
+                let price = match item.last_price {
+                    Some(price) => format!("{} {}", price, item.currency()),
+                    None => "?".to_string(),
+                };
+                if item.is_reached() {
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("🔔 {}", price));
+                } else {
+                    ui.label(price);
+                }
+                ui.label("cel");
+                let mut target = item.target.to_f64();
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut target)
+                            .speed(0.01)
+                            .max_decimals(2),
+                    )
+                    .changed()
+                {
+                    retarget = Some((index, Money::from_f64(target).max(Money::ZERO)));
+                }
+                if ui
+                    .small_button("🔍")
+                    .on_hover_text("Pokaż produkt")
+                    .clicked()
+                {
+                    open = Some((item.country, item.ean.clone()));
+                }
+                if ui
+                    .small_button("✖")
+                    .on_hover_text("Przestań obserwować")
+                    .clicked()
+                {
+                    removed = Some((item.country, item.ean.clone()));
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            if self.watch_task.is_some() {
+                ui.spinner();
+            } else if ui.button("Sprawdź teraz").clicked() {
+                self.watchlist.recheck_all();
+            }
+            ui.weak(format!(
+                "sprawdzane co {} min",
+                watchlist::CHECK_INTERVAL_MINUTES
+            ));
+        });
+
+        if let Some((index, target)) = retarget {
+            if self.watchlist.set_target(index, target) {
+                self.save_watchlist();
+            }
+        }
+        if let Some((country, ean)) = removed {
+            self.watchlist.remove(country, &ean);
+            self.save_watchlist();
+        }
+        if let Some((country, ean)) = open {
+            self.country = country;
+            self.ean = ean;
+            self.start_lookup(ui.ctx());
+        }
+    }
+}
@@ -1 +1,2 @@
 pub mod dmhelper;
+pub mod status;
@@ -0,0 +1,85 @@
+use chrono::{DateTime, Local};
+use egui::Ui;
+use std::{collections::VecDeque, fmt::Display};
+
+/// How many messages are kept before the oldest ones are dropped.
+const MAX_ENTRIES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    /// Something the user waited for, e.g. a price drop.
+    Alert,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatusEntry {
+    pub level: Level,
+    pub message: String,
+    pub at: DateTime<Local>,
+}
+
+/// Messages shown in the status area until the user dismisses them.
+#[derive(Debug, Default)]
+pub struct StatusLog {
+    entries: VecDeque<StatusEntry>,
+}
+
+impl StatusLog {
+    pub fn info(&mut self, message: impl Display) {
+        self.push(Level::Info, message.to_string());
+    }
+
+    pub fn alert(&mut self, message: impl Display) {
+        self.push(Level::Alert, message.to_string());
+    }
+
+    /// Records a failure; `context` says what was being done, e.g.
+    /// `"Zapis koszyka"`.
+    pub fn error(&mut self, context: &str, error: impl Display) {
+        self.push(Level::Error, format!("{}: {}", context, error));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn push(&mut self, level: Level, message: String) {
+        self.entries.push_back(StatusEntry {
+            level,
+            message,
+            at: Local::now(),
+        });
+        while self.entries.len() > MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Draws the messages, newest first, each with a dismiss button.
+    pub fn show(&mut self, ui: &mut Ui) {
+        let mut dismissed = None;
+        for (index, entry) in self.entries.iter().enumerate().rev() {
+            ui.horizontal(|ui| {
+                if ui.small_button("✖").clicked() {
+                    dismissed = Some(index);
+                }
+                ui.weak(entry.at.format("%H:%M:%S").to_string());
+                match entry.level {
+                    Level::Info => ui.label(entry.message.as_str()),
+                    Level::Alert => ui
+                        .colored_label(ui.visuals().warn_fg_color, format!("🔔 {}", entry.message)),
+                    Level::Error => {
+                        ui.colored_label(ui.visuals().error_fg_color, entry.message.as_str())
+                    }
+                };
+            });
+        }
+        if let Some(index) = dismissed {
+            self.entries.remove(index);
+        }
+        if self.entries.len() > 1 && ui.small_button("Wyczyść wszystkie").clicked() {
+            self.entries.clear();
+        }
+    }
+}